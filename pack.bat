@@ -1,3 +1,3 @@
 cargo build --release
 del epar.zip
-7z a -tzip epar.zip ./target/release/exclusively_polygons_alongside_rhythms.exe music/*.*
\ No newline at end of file
+7z a -tzip epar.zip ./target/release/exclusively_polygons_alongside_rhythms.exe music/*.* sfx/*.* midi/*.*
\ No newline at end of file
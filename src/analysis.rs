@@ -0,0 +1,224 @@
+//! Offline analysis of whole songs: onsets, loudness and an estimated tempo.\
+//! Used to draft charts and to drive generated levels, not for anything live (see `sound::Spectrum` for that).
+use std::f32::consts::PI;
+
+use soloud::{Soloud, SoloudFlag, Backend, Wav, AudioExt, LoadExt};
+
+use crate::Possibly;
+
+const SAMPLE_RATE: u32 = 44100;
+/// Samples per analysis frame. Must be a power of two for the FFT.
+const WINDOW: usize = 1024;
+/// Samples between the starts of two frames.
+const HOP: usize = 512;
+/// Frames on each side that an onset has to be the loudest of.
+const PEAK_RADIUS: usize = 3;
+/// Frames on each side used for the adaptive onset threshold.
+const MEAN_RADIUS: usize = 16;
+/// How far above the local mean flux a peak has to be to count as an onset.
+const ONSET_RATIO: f32 = 1.4;
+const MIN_BPM: f32 = 70.0;
+const MAX_BPM: f32 = 180.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Onset {
+    /// Seconds into the song.
+    pub time: f32,
+    /// 0-1, relative to the strongest onset in the song.
+    pub strength: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    pub bpm: f32,
+    /// Seconds into the song of the first beat of the detected grid.
+    pub first_beat: f32,
+    pub onsets: Vec<Onset>,
+    /// RMS loudness per frame, 0-1 relative to the loudest frame.
+    pub loudness: Vec<f32>,
+    /// Seconds between two `loudness` entries.
+    pub frame_secs: f32,
+    /// Length of the song in seconds.
+    pub length: f32,
+}
+impl Analysis {
+    /// Decodes and analyzes a song file. Slow; expect a few seconds for a full song.
+    pub fn of_file(path: &str) -> Possibly<Self> {
+        Ok(Self::of_samples(&decode(path)?, SAMPLE_RATE as f32))
+    }
+    pub fn of_samples(samples: &[f32], sample_rate: f32) -> Self {
+        let frames = samples.len().saturating_sub(WINDOW) / HOP;
+        let frame_secs = HOP as f32 / sample_rate;
+        let hann = (0..WINDOW).map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / WINDOW as f32).cos()).collect::<Vec<f32>>();
+
+        let mut flux = Vec::with_capacity(frames);
+        let mut loudness = Vec::with_capacity(frames);
+        let mut prev = vec![0.0; WINDOW / 2];
+        let mut re = vec![0.0; WINDOW];
+        let mut im = vec![0.0; WINDOW];
+        for f in 0..frames {
+            let frame = &samples[f * HOP..f * HOP + WINDOW];
+            loudness.push((frame.iter().map(|s| s * s).sum::<f32>() / WINDOW as f32).sqrt());
+            for i in 0..WINDOW {
+                re[i] = frame[i] * hann[i];
+                im[i] = 0.0;
+            }
+            fft(&mut re, &mut im);
+            let mut total = 0.0;
+            for k in 0..WINDOW / 2 {
+                // log magnitudes keep quiet-but-sharp hits from being drowned out by loud sustained ones
+                let mag = (re[k].hypot(im[k])).ln_1p();
+                total += (mag - prev[k]).max(0.0);
+                prev[k] = mag;
+            }
+            flux.push(total);
+        }
+        normalize(&mut loudness);
+
+        let onsets = pick_onsets(&flux, frame_secs);
+        let (bpm, first_beat) = estimate_tempo(&flux, frame_secs);
+        Analysis { bpm, first_beat, onsets, loudness, frame_secs, length: samples.len() as f32 / sample_rate }
+    }
+    /// Loudness at `secs` seconds into the song, 0 outside of it.
+    pub fn loudness_at(&self, secs: f32) -> f32 {
+        if secs < 0.0 { return 0.0; }
+        *self.loudness.get((secs / self.frame_secs) as usize).unwrap_or(&0.0)
+    }
+    /// The offset (in beats) that puts beat 0 on the first detected beat, as returned by level loaders.
+    pub fn offset(&self) -> f32 {
+        -self.first_beat * self.bpm / 60.0
+    }
+}
+
+/// Renders a whole file to mono samples by mixing it on a Soloud instance with no audio device.
+pub fn decode(path: &str) -> Possibly<Vec<f32>> {
+    let mut sl = Soloud::new(SoloudFlag::empty(), Backend::Null, SAMPLE_RATE, WINDOW as u32, 1)?;
+    let mut wav = Wav::default();
+    wav.load(path)?;
+    let total = (wav.length() * SAMPLE_RATE as f64) as usize;
+    sl.play(&wav);
+    let mut samples = vec![0.0; total];
+    for chunk in samples.chunks_mut(WINDOW * 16) {
+        sl.mix(chunk);
+    }
+    Ok(samples)
+}
+
+/// In-place iterative radix-2 FFT. `re.len()` must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let ang = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (wr, wi) = ((ang * k as f32).cos(), (ang * k as f32).sin());
+                let (a, b) = (start + k, start + k + len / 2);
+                let (tr, ti) = (re[b] * wr - im[b] * wi, re[b] * wi + im[b] * wr);
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+fn normalize(values: &mut [f32]) {
+    let max = values.iter().copied().fold(0.0, f32::max);
+    if max > 0.0 {
+        values.iter_mut().for_each(|v| *v /= max);
+    }
+}
+
+/// Peaks of the flux that stand out from their surroundings.
+fn pick_onsets(flux: &[f32], frame_secs: f32) -> Vec<Onset> {
+    let mut onsets = vec![];
+    for i in 0..flux.len() {
+        let near = i.saturating_sub(PEAK_RADIUS)..(i + PEAK_RADIUS + 1).min(flux.len());
+        let around = i.saturating_sub(MEAN_RADIUS)..(i + MEAN_RADIUS + 1).min(flux.len());
+        let mean = flux[around.clone()].iter().sum::<f32>() / around.len() as f32;
+        if flux[near].iter().all(|f| *f <= flux[i]) && flux[i] > mean * ONSET_RATIO {
+            onsets.push(Onset { time: i as f32 * frame_secs, strength: flux[i] });
+        }
+    }
+    let max = onsets.iter().map(|o| o.strength).fold(0.0, f32::max);
+    if max > 0.0 {
+        onsets.iter_mut().for_each(|o| o.strength /= max);
+    }
+    onsets
+}
+
+/// Autocorrelates the flux to find the beat period, then finds the phase that lines up with the most flux.
+fn estimate_tempo(flux: &[f32], frame_secs: f32) -> (f32, f32) {
+    let mean = flux.iter().sum::<f32>() / flux.len().max(1) as f32;
+    let centered = flux.iter().map(|f| f - mean).collect::<Vec<f32>>();
+    let min_lag = (60.0 / MAX_BPM / frame_secs) as usize;
+    let max_lag = (60.0 / MIN_BPM / frame_secs) as usize + 1;
+    let corr = |lag: usize| -> f32 {
+        if lag >= centered.len() { return 0.0; }
+        centered.iter().zip(&centered[lag..]).map(|(a, b)| a * b).sum()
+    };
+    // weighted towards 120bpm, otherwise a strong downbeat tends to win at half tempo
+    let prior = |lag: usize| (-0.5 * (60.0 / (lag as f32 * frame_secs) / 120.0).log2().powi(2)).exp();
+    let scores = (min_lag..=max_lag).map(|lag| corr(lag) * prior(lag)).collect::<Vec<f32>>();
+    let best = scores.iter().enumerate().fold(0, |best, (i, s)| if *s > scores[best] { i } else { best });
+    // parabolic interpolation between neighbouring lags for a sub-frame period
+    let refine = if best > 0 && best + 1 < scores.len() {
+        let (a, b, c) = (scores[best - 1], scores[best], scores[best + 1]);
+        let denom = a - 2.0 * b + c;
+        if denom != 0.0 { 0.5 * (a - c) / denom } else { 0.0 }
+    } else { 0.0 };
+    let period = (min_lag + best) as f32 + refine;
+    if period <= 0.0 { return (120.0, 0.0); }
+
+    let phases = period.ceil() as usize;
+    let phase_score = |phase: usize| -> f32 {
+        let mut total = 0.0;
+        let mut t = phase as f32;
+        while (t as usize) < flux.len() {
+            total += flux[t as usize];
+            t += period;
+        }
+        total
+    };
+    let phase = (0..phases).fold(0, |best, p| if phase_score(p) > phase_score(best) { p } else { best });
+    let (period, phase) = fit_grid(flux, period, phase as f32);
+    (60.0 / (period * frame_secs), phase * frame_secs)
+}
+
+/// Small period errors add up over a whole song, so this snaps each beat of the rough grid to the strongest flux near it
+/// and fits a line through those (least squares, weighted by flux).
+fn fit_grid(flux: &[f32], period: f32, phase: f32) -> (f32, f32) {
+    let reach = (period / 4.0) as usize;
+    let (mut sw, mut sk, mut st, mut skk, mut skt) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    let mut k = 0.0;
+    while ((phase + k * period) as usize) < flux.len() {
+        let guess = (phase + k * period) as usize;
+        let near = guess.saturating_sub(reach)..(guess + reach + 1).min(flux.len());
+        let t = near.clone().fold(guess, |best, i| if flux[i] > flux[best] { i } else { best }) as f32;
+        let w = flux[t as usize];
+        sw += w; sk += w * k; st += w * t; skk += w * k * k; skt += w * k * t;
+        k += 1.0;
+    }
+    let denom = sw * skk - sk * sk;
+    if denom.abs() < f32::EPSILON { return (period, phase); }
+    let fitted = (sw * skt - sk * st) / denom;
+    let start = (st - fitted * sk) / sw;
+    // the fit can land a little before the song starts; move it up a beat
+    (fitted, if start < 0.0 { start + fitted } else { start })
+}
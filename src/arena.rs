@@ -0,0 +1,50 @@
+//! The rectangle (in world space) the player is kept inside. Levels can ease it around to close walls in on the player.
+use macroquad::{prelude::{Vec2, vec2, Rect, Color}, shapes::draw_rectangle_lines};
+
+use crate::{tween::Tween, utils::{screen_size, acmul}};
+
+/// An eased change of the bounds from wherever they are when it starts to `to`.
+pub type ArenaMove = Tween<Rect>;
+
+#[derive(Clone)]
+pub struct Arena {
+    pub bounds: Rect,
+    mv: Option<ArenaMove>,
+}
+impl Default for Arena {
+    /// The whole screen.
+    fn default() -> Self {
+        Arena { bounds: Rect::new(0.0, 0.0, screen_size().x, screen_size().y), mv: None }
+    }
+}
+impl Arena {
+    /// The whole screen shrunk by `inset` on every side.
+    pub fn inset(inset: f32) -> Rect {
+        Rect::new(inset, inset, screen_size().x - inset * 2.0, screen_size().y - inset * 2.0)
+    }
+    /// Starts a move, replacing any unfinished one.
+    pub fn animate(&mut self, mv: ArenaMove) {
+        self.mv = Some(mv);
+    }
+    /// Advances the move to `time` (in beats), dropping it once it's finished.
+    pub fn update(&mut self, time: f32) {
+        let Some(mv) = &mut self.mv else { return; };
+        if let Some(bounds) = mv.step(time, self.bounds) { self.bounds = bounds; }
+        if mv.finished(time) { self.mv = None; }
+    }
+    /// Moves a circle the least it takes to be fully inside. Walls closing in push the player along with them.\
+    /// If the arena is smaller than the circle, it ends up in the middle.
+    pub fn clamp(&self, pos: Vec2, rad: f32) -> Vec2 {
+        let min = vec2(self.bounds.x, self.bounds.y) + rad;
+        let max = vec2(self.bounds.right(), self.bounds.bottom()) - rad;
+        let center = self.bounds.center();
+        vec2(
+            if min.x > max.x { center.x } else { pos.x.clamp(min.x, max.x) },
+            if min.y > max.y { center.y } else { pos.y.clamp(min.y, max.y) },
+        )
+    }
+    pub fn draw(&self, color: Color) {
+        let b = self.bounds;
+        draw_rectangle_lines(b.x, b.y, b.w, b.h, 4.0, acmul(color, 0.6));
+    }
+}
@@ -0,0 +1,69 @@
+//! Uniform grid over obstacle bounds, so collision only runs the exact (narrowphase) test on obstacles near the player.
+use std::collections::HashMap;
+
+use macroquad::prelude::{Rect, Vec2, vec2};
+
+/// Bounds of obstacles that don't know their own; they're tested against everything.
+pub const EVERYWHERE: Rect = Rect { x: f32::NEG_INFINITY, y: f32::NEG_INFINITY, w: f32::INFINITY, h: f32::INFINITY };
+/// Obstacles covering more cells than this go in the everywhere list instead, which is cheaper than filling the grid.
+const MAX_CELLS: i64 = 64;
+
+pub fn circle_bounds(pos: Vec2, rad: f32) -> Rect {
+    Rect::new(pos.x - rad, pos.y - rad, rad * 2.0, rad * 2.0)
+}
+/// Bounds of a line `thickness` wide.
+pub fn segment_bounds(start: Vec2, end: Vec2, thickness: f32) -> Rect {
+    let min = start.min(end) - thickness / 2.0;
+    let max = start.max(end) + thickness / 2.0;
+    Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+pub struct Broadphase {
+    pub cell: f32,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    everywhere: Vec<usize>,
+}
+impl Broadphase {
+    /// Sorts everything into cells `cell` pixels wide. `bounds` are by index, with `None` for things that never collide.
+    pub fn build(cell: f32, bounds: impl IntoIterator<Item = Option<Rect>>) -> Self {
+        let mut grid = Broadphase { cell, cells: HashMap::new(), everywhere: vec![] };
+        for (idx, rect) in bounds.into_iter().enumerate() {
+            let Some(rect) = rect else { continue; };
+            match grid.cell_range(rect) {
+                Some((min, max)) if (max.0 - min.0 + 1) * (max.1 - min.1 + 1) <= MAX_CELLS => {
+                    for x in min.0..=max.0 {
+                        for y in min.1..=max.1 {
+                            grid.cells.entry((x, y)).or_default().push(idx);
+                        }
+                    }
+                }
+                _ => grid.everywhere.push(idx),
+            }
+        }
+        grid
+    }
+    /// Cells covered by `rect`, `None` if it's unbounded.
+    fn cell_range(&self, rect: Rect) -> Option<((i64, i64), (i64, i64))> {
+        if !(rect.x.is_finite() && rect.y.is_finite() && rect.w.is_finite() && rect.h.is_finite()) { return None; }
+        let min = (vec2(rect.x, rect.y) / self.cell).floor();
+        let max = (vec2(rect.right(), rect.bottom()) / self.cell).floor();
+        Some(((min.x as i64, min.y as i64), (max.x as i64, max.y as i64)))
+    }
+    /// Indices of everything whose bounds might overlap `area`, each once and in order.
+    pub fn query(&self, area: Rect) -> Vec<usize> {
+        let mut found = self.everywhere.clone();
+        match self.cell_range(area) {
+            Some((min, max)) if (max.0 - min.0 + 1) * (max.1 - min.1 + 1) <= MAX_CELLS => {
+                for x in min.0..=max.0 {
+                    for y in min.1..=max.1 {
+                        found.extend(self.cells.get(&(x, y)).into_iter().flatten());
+                    }
+                }
+            }
+            _ => found.extend(self.cells.values().flatten()),
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
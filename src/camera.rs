@@ -0,0 +1,127 @@
+//! Everything in a level (obstacles, the player, collisions) lives in world space. The camera only changes the view,
+//! so zooming, turning and shaking it never moves a hitbox away from what's drawn.\
+//! Shake, jerks and float are screen effects, in pixels whatever the zoom. Position, zoom and rotation move the view
+//! of the world, and can be eased by levels through `UpdateAccumulator`.
+use macroquad::{prelude::{Vec2, vec2, Camera2D}, camera::Camera as _, rand::gen_range};
+
+use crate::{tween::{Tween, Lerp}, utils::{screen_center, screen_size, virtual_camera}};
+
+/// Something about the camera to ease to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraKey {
+    /// World position in the middle of the screen.
+    Pos(Vec2),
+    /// 2 shows everything twice as big.
+    Zoom(f32),
+    /// Radians, turning the world clockwise on screen.
+    Rot(f32),
+}
+impl CameraKey {
+    fn same_kind(&self, other: &CameraKey) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+impl Lerp for CameraKey {
+    fn lerp(self, to: CameraKey, t: f32) -> CameraKey {
+        match (self, to) {
+            (CameraKey::Pos(a), CameraKey::Pos(b)) => CameraKey::Pos(a.lerp(b, t)),
+            (CameraKey::Zoom(a), CameraKey::Zoom(b)) => CameraKey::Zoom(a.lerp(b, t)),
+            (CameraKey::Rot(a), CameraKey::Rot(b)) => CameraKey::Rot(a.lerp(b, t)),
+            _ => to,
+        }
+    }
+}
+
+/// An eased move from wherever the camera is when it starts to `to`.
+pub type CameraMove = Tween<CameraKey>;
+
+#[derive(Clone)]
+pub struct Camera {
+    pub pos: Vec2,
+    pub zoom: f32,
+    pub rot: f32,
+    /// Extra zoom that springs back, for punches.
+    pub punch: f32,
+    /// Knocks the view aside, settling back over a few frames.
+    pub jerk: Vec2,
+    /// Random offset up to this many pixels, settling back over a few frames.
+    pub shake: f32,
+    /// Sways the view in a slow loop this many pixels wide.
+    pub float: f32,
+    /// How much the bass adds to `float`, see `UpdateAccumulator::react_float`.
+    pub react_float: f32,
+    moves: Vec<CameraMove>,
+}
+impl Default for Camera {
+    fn default() -> Self {
+        Camera { pos: screen_center(), zoom: 1.0, rot: 0.0, punch: 0.0, jerk: Vec2::ZERO, shake: 0.0, float: 0.0, react_float: 0.0, moves: vec![] }
+    }
+}
+impl Camera {
+    /// Eases jerk, shake and punch back towards rest. Once per frame.
+    pub fn settle(&mut self) {
+        self.jerk *= 0.8;
+        self.shake *= 0.95;
+        self.punch *= 0.9;
+    }
+    /// Starts a move, replacing any unfinished move of the same kind.
+    pub fn animate(&mut self, mv: CameraMove) {
+        self.moves.retain(|m| !m.to.same_kind(&mv.to));
+        self.moves.push(mv);
+    }
+    fn get(&self, like: CameraKey) -> CameraKey {
+        match like {
+            CameraKey::Pos(_) => CameraKey::Pos(self.pos),
+            CameraKey::Zoom(_) => CameraKey::Zoom(self.zoom),
+            CameraKey::Rot(_) => CameraKey::Rot(self.rot),
+        }
+    }
+    fn set(&mut self, key: CameraKey) {
+        match key {
+            CameraKey::Pos(pos) => self.pos = pos,
+            CameraKey::Zoom(zoom) => self.zoom = zoom,
+            CameraKey::Rot(rot) => self.rot = rot,
+        }
+    }
+    /// Advances moves to `time` (in beats), dropping finished ones.
+    pub fn update(&mut self, time: f32) {
+        let mut moves = std::mem::take(&mut self.moves);
+        moves.retain_mut(|mv| {
+            if let Some(key) = mv.step(time, self.get(mv.to)) { self.set(key); }
+            !mv.finished(time)
+        });
+        self.moves = moves;
+    }
+    /// Where the camera is looking this frame. Shake is random, so take one view per frame and draw everything through it.
+    pub fn view(&self, time: f32, bass: f32) -> View {
+        let shift = self.jerk
+            + vec2(gen_range(-self.shake, self.shake), gen_range(-self.shake, self.shake))
+            + vec2(time.sin(), (time * 1.2).sin()) * (self.float + bass * self.react_float);
+        let size = screen_size();
+        let mut camera = virtual_camera();
+        camera.target = self.pos;
+        camera.zoom *= self.zoom * (1.0 + self.punch);
+        camera.rotation = self.rot.to_degrees();
+        // the offset is in clip space, where y points up
+        camera.offset = shift / size * vec2(2.0, -2.0);
+        View { camera }
+    }
+}
+
+/// The transform between world space and the screen for one frame.\
+/// "Screen" is the virtual screen from `utils::letterbox`, not window pixels; `utils::to_virtual` gets there from the mouse.
+#[derive(Clone, Copy)]
+pub struct View {
+    pub camera: Camera2D,
+}
+impl View {
+    pub fn to_screen(self, world: Vec2) -> Vec2 {
+        // Camera2D's own version assumes the camera fills the window
+        let clip = self.camera.matrix().project_point3(world.extend(0.0)).truncate();
+        (vec2(clip.x, -clip.y) + 1.0) / 2.0 * screen_size()
+    }
+    pub fn to_world(self, screen: Vec2) -> Vec2 {
+        let clip = screen / screen_size() * 2.0 - 1.0;
+        self.camera.matrix().inverse().project_point3(vec2(clip.x, -clip.y).extend(0.0)).truncate()
+    }
+}
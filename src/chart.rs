@@ -0,0 +1,194 @@
+//! Charts are plain-text lists of beat-stamped markers for a song, each on a "lane" that picks what gets spawned.\
+//! They can be drafted from a song's onsets and turned into `GSEvent`s for a playable level.
+//!
+//! ```text
+//! # comments start with #
+//! audio music/smoke.mp3
+//! bpm 74
+//! offset -2.07
+//! 0 2
+//! 0.5 0
+//! ```
+use std::{fs, f32::consts::TAU};
+
+use macroquad::{
+    prelude::{vec2, KeyCode, is_key_pressed, WHITE, GRAY, BLACK},
+    rand::gen_range,
+    shapes::draw_rectangle,
+    text::draw_text
+};
+
+use crate::{
+    Possibly,
+    analysis::Analysis,
+    game::{GSEvent, UpdateAccumulator, Accumulatee},
+    game_objects::Pellet,
+    utils::{cmul, screen_width, screen_height, clear_virtual},
+    spawners::{LaserSpawner, BombSideSpawner}
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marker {
+    pub beat: f32,
+    pub lane: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Chart {
+    pub audio: String,
+    pub bpm: f32,
+    /// Same meaning as the offset returned by level loaders.
+    pub offset: f32,
+    pub markers: Vec<Marker>,
+}
+
+/// What each lane spawns, and how many beats early it has to be spawned so that it hits on the marker.\
+/// Lanes past the end wrap around.
+pub fn lane_spawner(lane: usize) -> (f32, Box<dyn Accumulatee>) {
+    match lane % LANES {
+        0 => (0.0, Box::new(|accum: &mut UpdateAccumulator, _| {
+            let pos = vec2(gen_range(0.0, screen_width()), gen_range(0.0, screen_height()));
+            let count = 12;
+            let phase = gen_range(0.0, 1.0);
+            for i in 0..count {
+                let angle = (i as f32 / count as f32 + phase) * TAU;
+                accum.obst(Pellet::new(pos, vec2(angle.cos(), angle.sin()) * 200.0, 10.0));
+            }
+        })),
+        1 => (2.0, Box::new(LaserSpawner::new(2.0, 1.0, 45.0, 10.0))),
+        _ => (2.0, Box::new(BombSideSpawner::new(12, 200.0, 12.5, 2.0))),
+    }
+}
+/// Number of distinct lanes `lane_spawner` knows about.
+pub const LANES: usize = 3;
+
+impl Chart {
+    /// Places a marker on every detected onset, snapped to `1 / subdivision` beats.\
+    /// Stronger onsets go on higher lanes, so the biggest hits get the biggest attacks.
+    pub fn draft(audio: &str, analysis: &Analysis, subdivision: f32) -> Self {
+        let offset = analysis.offset();
+        let mut markers: Vec<(Marker, f32)> = vec![];
+        for onset in &analysis.onsets {
+            let beat = snap(onset.time * analysis.bpm / 60.0 + offset, subdivision);
+            let lane = ((onset.strength * LANES as f32) as usize).min(LANES - 1);
+            match markers.last_mut() {
+                // two onsets snapping to the same beat; keep the stronger one
+                Some((last, strength)) if last.beat == beat => if onset.strength > *strength {
+                    *last = Marker { beat, lane };
+                    *strength = onset.strength;
+                },
+                _ => markers.push((Marker { beat, lane }, onset.strength))
+            }
+        }
+        Chart {
+            audio: audio.to_string(),
+            bpm: analysis.bpm,
+            offset,
+            markers: markers.into_iter().map(|(m, _)| m).collect()
+        }
+    }
+    pub fn events(&self) -> Vec<GSEvent> {
+        self.markers.iter().map(|m| {
+            let (lead, spawner) = lane_spawner(m.lane);
+            GSEvent(m.beat - lead, spawner)
+        }).collect()
+    }
+    pub fn save(&self, path: &str) -> Possibly<()> {
+        let mut out = format!("# epar chart\naudio {}\nbpm {}\noffset {}\n", self.audio, self.bpm, self.offset);
+        for m in &self.markers {
+            out += &format!("{} {}\n", m.beat, m.lane);
+        }
+        fs::write(path, out)?;
+        Ok(())
+    }
+    pub fn load(path: &str) -> Possibly<Self> {
+        let mut chart = Chart::default();
+        for (idx, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let (key, value) = line.split_once(' ').ok_or_else(|| format!("{path}:{}: expected two fields", idx + 1))?;
+            let value = value.trim();
+            match key {
+                "audio" => chart.audio = value.to_string(),
+                "bpm" => chart.bpm = value.parse()?,
+                "offset" => chart.offset = value.parse()?,
+                beat => chart.markers.push(Marker { beat: beat.parse()?, lane: value.parse()? }),
+            }
+        }
+        if chart.audio.is_empty() || chart.bpm <= 0.0 {
+            return Err(format!("{path}: missing audio or bpm").into());
+        }
+        Ok(chart)
+    }
+}
+
+pub fn snap(beat: f32, subdivision: f32) -> f32 {
+    (beat * subdivision).round() / subdivision
+}
+
+/// Keys that record a marker on each lane, in lane order.
+pub const RECORD_KEYS: [KeyCode; LANES] = [KeyCode::J, KeyCode::K, KeyCode::L];
+pub const SUBDIVISIONS: [f32; 6] = [1.0, 2.0, 3.0, 4.0, 6.0, 8.0];
+
+/// Tap-to-chart: records a marker whenever a lane key is pressed while the song plays.\
+/// Up/down changes the subdivision taps snap to, backspace drops the last marker.
+pub struct Recorder {
+    pub chart: Chart,
+    /// Where the chart gets saved to once the song is over.
+    pub path: String,
+    pub subdivision: usize,
+    /// Per-lane highlight, set on tap and faded out.
+    flash: [f32; LANES],
+}
+impl Recorder {
+    pub fn new(audio: &str, bpm: f32, offset: f32, path: &str) -> Self {
+        Recorder {
+            chart: Chart { audio: audio.to_string(), bpm, offset, markers: vec![] },
+            path: path.to_string(),
+            subdivision: 3,
+            flash: [0.0; LANES],
+        }
+    }
+    pub fn subdivision(&self) -> f32 { SUBDIVISIONS[self.subdivision] }
+    pub fn update(&mut self, beat: f32, frame_time: f32) {
+        if is_key_pressed(KeyCode::Up) { self.subdivision = (self.subdivision + 1).min(SUBDIVISIONS.len() - 1); }
+        if is_key_pressed(KeyCode::Down) { self.subdivision = self.subdivision.saturating_sub(1); }
+        if is_key_pressed(KeyCode::Backspace) { self.chart.markers.pop(); }
+        for (lane, key) in RECORD_KEYS.into_iter().enumerate() {
+            self.flash[lane] = (self.flash[lane] - frame_time * 4.0).max(0.0);
+            if !is_key_pressed(key) { continue; }
+            let marker = Marker { beat: snap(beat, self.subdivision()), lane };
+            // mashing a key within one subdivision only counts once
+            if !self.chart.markers.contains(&marker) {
+                self.chart.markers.push(marker);
+            }
+            self.flash[lane] = 1.0;
+        }
+    }
+    pub fn draw(&self, beat: f32) {
+        clear_virtual(BLACK);
+        let lane_width = screen_width() / LANES as f32;
+        // markers scroll up from the bottom, 100 pixels per beat
+        let now_y = screen_height() - 100.0;
+        for (lane, flash) in self.flash.iter().enumerate() {
+            let x = lane as f32 * lane_width;
+            draw_rectangle(x + 5.0, 0.0, lane_width - 10.0, screen_height(), cmul(WHITE, 0.05 + flash * 0.2));
+            draw_text(&format!("{:?}", RECORD_KEYS[lane]), x + lane_width / 2.0 - 10.0, screen_height() - 20.0, 40.0, GRAY);
+        }
+        draw_rectangle(0.0, now_y - 1.0, screen_width(), 2.0, WHITE);
+        for m in &self.chart.markers {
+            let y = now_y - (beat - m.beat) * 100.0;
+            if y < -10.0 || y > screen_height() { continue; }
+            draw_rectangle(m.lane as f32 * lane_width + 20.0, y - 5.0, lane_width - 40.0, 10.0, WHITE);
+        }
+        let txt = format!("Recording {} | beat {:.2} | 1/{} | {} markers", self.path, beat, self.subdivision(), self.chart.markers.len());
+        draw_text(&txt, 10.0, 30.0, 30.0, WHITE);
+    }
+    pub fn save(&mut self) -> Possibly<()> {
+        self.chart.markers.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        if let Some(dir) = std::path::Path::new(&self.path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.chart.save(&self.path)
+    }
+}
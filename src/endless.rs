@@ -0,0 +1,105 @@
+//! Endless mode: any song, looped forever, with patterns generated from its analysis instead of a written level.
+use std::rc::Rc;
+
+use macroquad::{prelude::{Vec2, Rect, Color, WHITE}, rand::gen_range};
+
+use crate::{
+    analysis::Analysis,
+    chart::{lane_spawner, LANES},
+    game::{GameState, UpdateAccumulator, Accumulatee, ModifyArgs, GSEvent},
+    game_objects::{Obstacle, Player},
+    spawners::{HorLaserSpawner, VertLaserSpawner},
+    utils::{recip_ease, cmul}
+};
+
+/// Number of patterns `pattern` knows about, easiest first.
+pub const PATTERNS: usize = LANES + 2;
+
+/// The attack vocabulary of endless mode. Harder patterns only show up once the difficulty is high enough.
+pub fn pattern(idx: usize) -> Box<dyn Accumulatee> {
+    match idx {
+        i if i < LANES => lane_spawner(i).1,
+        i if i == LANES => Box::new(HorLaserSpawner::new(1.0, 0.5, 30.0, 5.0)),
+        _ => Box::new(|accum: &mut UpdateAccumulator, args: ModifyArgs| {
+            for _ in 0..3 {
+                VertLaserSpawner::new(1.0, 0.5, 25.0, 5.0).run(accum, args);
+            }
+        }),
+    }
+}
+
+/// Spawns patterns every beat (or half beat once things get going), forever.\
+/// Quiet parts of the song spawn less, loud parts spawn more, and strong onsets get an extra hit.
+#[derive(Clone)]
+pub struct Endless {
+    pub analysis: Rc<Analysis>,
+    /// Beats until the difficulty is halfway to its maximum.
+    pub ramp: f32,
+    /// The level's offset, to find where in the song a beat is.
+    pub offset: f32,
+    time: f32,
+    /// Next step to spawn on, in beats since this started.
+    next: f32,
+}
+impl Endless {
+    pub fn new(analysis: Rc<Analysis>, offset: f32) -> Self {
+        Endless { analysis, ramp: 64.0, offset, time: 0.0, next: 0.0 }
+    }
+    /// 0 at the start, approaching 1 the longer the song has been going.
+    pub fn difficulty(&self) -> f32 {
+        recip_ease(self.time / self.ramp)
+    }
+    /// Seconds into the (looping) song for a beat since this started.
+    fn song_secs(&self, time: f32) -> f32 {
+        ((time - self.offset) * 60.0 / self.analysis.bpm).rem_euclid(self.analysis.length.max(1.0))
+    }
+    fn step(&self, to_add: &mut UpdateAccumulator, step_len: f32) {
+        let difficulty = self.difficulty();
+        let secs = self.song_secs(self.next);
+        let loudness = self.analysis.loudness_at(secs);
+        // faster songs get fewer patterns per beat, so the patterns per second stay reasonable
+        let tempo = (120.0 / self.analysis.bpm).clamp(0.5, 1.5);
+        let chance = (0.2 + 0.8 * difficulty) * (0.25 + loudness) * tempo * step_len;
+        let args = ModifyArgs::new(to_add.time()).step((self.next / step_len) as usize);
+        if gen_range(0.0, 1.0) < chance {
+            let available = 1 + (difficulty * (PATTERNS - 1) as f32).round() as usize;
+            pattern(gen_range(0, available)).run(to_add, args);
+        }
+        let until = secs + step_len * 60.0 / self.analysis.bpm;
+        if self.analysis.onsets.iter().any(|o| o.strength > 0.8 && (secs..until).contains(&o.time)) {
+            pattern(0).run(to_add, args);
+        }
+    }
+}
+impl Obstacle for Endless {
+    fn update(&mut self, to_add: &mut UpdateAccumulator, dtime: f32, time: f32, dease: f32, ease: f32) {
+        self.time = time;
+        while self.next <= self.time {
+            let step_len = if self.difficulty() > 0.5 { 0.5 } else { 1.0 };
+            // the step was due at `next`, a little before now
+            let at = to_add.time() - (self.time - self.next);
+            to_add.at(at, |to_add| self.step(to_add, step_len));
+            self.next += step_len;
+        }
+    }
+    fn draw(&self, color: Color) { }
+    fn box_clone(&self) -> Box<dyn Obstacle> { Box::new(self.clone()) }
+    fn collides(&self, player: Player) -> bool { false }
+    fn bounds(&self) -> Option<Rect> { None }
+    fn should_kill(&mut self) -> bool { false }
+}
+
+/// Analyzes `path` and fills the level with an `Endless` generator. Returns the offset and bpm, like `EparLevel::info`.\
+/// Analysis takes a few seconds for a full song.
+pub fn endless(state: &mut GameState, path: &str) -> crate::Possibly<(f32, f32)> {
+    let analysis = Rc::new(Analysis::of_file(path)?);
+    let offset = analysis.offset();
+    state.instantly(|accum: &mut UpdateAccumulator, _| {
+        accum.fg(cmul(WHITE, 0.8));
+        accum.react_bg(0.15);
+    });
+    let generator = Endless::new(analysis.clone(), offset);
+    // starts on the song's first beat, so that steps land on beats
+    state.add_event(GSEvent::new(0.0, move |accum: &mut UpdateAccumulator, _| accum.obst(generator.clone())));
+    Ok((offset, analysis.bpm))
+}
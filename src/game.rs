@@ -1,10 +1,10 @@
 
 use std::error::Error;
 
-use macroquad::{prelude::{Vec2, Color, is_key_down, KeyCode, vec2, is_key_pressed, RED, SKYBLUE, WHITE}, window::{screen_width, screen_height, clear_background}, shapes::{draw_circle, draw_rectangle}, rand::gen_range, text::draw_text, miniquad::log::Level};
-use soloud::{Wav, AudioExt, LoadExt};
+use macroquad::{prelude::{Vec2, Rect, Color, is_key_down, KeyCode, vec2, is_key_pressed, RED, SKYBLUE, WHITE, BLACK, GRAY}, shapes::{draw_circle, draw_circle_lines, draw_rectangle}, rand::gen_range, text::{draw_text, measure_text}, camera::set_camera, miniquad::log::Level};
+use soloud::{WavStream, AudioExt, LoadExt};
 
-use crate::{game_objects::Obstacle, utils::{mix, centered_text_draw, acmul}, state_control::{EparLevel, EparState, ColorChange}, sound::Music};
+use crate::{game_objects::{Obstacle, Easing}, camera::{Camera, CameraMove, CameraKey, View}, arena::{Arena, ArenaMove}, broadphase::{Broadphase, circle_bounds, segment_bounds}, chart::{Chart, Recorder}, endless::endless, utils::{mix, centered_text_draw, acmul, sq, screen_width, screen_height, clear_virtual, virtual_camera}, state_control::{EparLevel, EparState, ColorChange, SpeedChange}, sound::{Music, SfxCreator, Sfx, Mixer, Preview, Metronome, FilterParam, Spectrum}};
 
 use super::game_objects::{Player, Obst};
 
@@ -17,6 +17,12 @@ pub fn hitdash_color() -> Color { mix(hit_color(), dash_color(), 0.5) }
 pub const COLLISION_DBG: bool = false;
 /// When `COLLISION_DBG` is enabled, specifies the size of the rectangles used for collision debugging.
 pub const COLLISION_FRAGMENT_SIZE: usize = 20;
+/// Size of the cells obstacles are sorted into before collision checks. About the size of a big pellet burst.
+pub const BROADPHASE_CELL_SIZE: f32 = 100.0;
+/// How close (in pixels, past touching) the player has to pass an obstacle to graze it, unless the level changes it.
+pub const GRAZE_MARGIN: f32 = 15.0;
+/// Points for each graze.
+pub const GRAZE_POINTS: u32 = 100;
 
 /// Extra arguments for specializing `StateModifier`s and `Accumulatee`s
 #[derive(Default, Clone, Copy)]
@@ -25,7 +31,11 @@ pub struct ModifyArgs {
     pub pos: Vec2,
     pub vel: Vec2,
     pub rad: f32,
-    pub time: f32
+    pub time: f32,
+    /// MIDI note number, for events imported from MIDI.
+    pub pitch: u8,
+    /// 0-1, for events imported from MIDI.
+    pub velocity: f32,
 }
 macro_rules! builder {
     ($name:tt: $type:ty) => {
@@ -38,11 +48,24 @@ impl ModifyArgs {
     builder!(pos: Vec2);
     builder!(vel: Vec2);
     builder!(rad: f32);
+    builder!(pitch: u8);
+    builder!(velocity: f32);
 }
 
 pub struct UpdateAccumulator {
     obstacles_to_add: Vec<Obst>,
     events: Vec<Box<dyn StateModifier>>,
+    sfx: Vec<Sfx>,
+    filters: Vec<(FilterParam, f32, f32)>,
+    stems: Vec<(usize, f32, f32)>,
+    stem_energy: Vec<f32>,
+    spectrum: Spectrum,
+    react_bg: Option<f32>,
+    react_float: Option<f32>,
+    graze_margin: Option<f32>,
+    camera_moves: Vec<CameraMove>,
+    arena_moves: Vec<ArenaMove>,
+    punch: f32,
     jerk: Vec2,
     bg: Option<Color>,
     fg: Option<Color>,
@@ -54,10 +77,25 @@ impl UpdateAccumulator {
     pub fn time(&self) -> f32 {
         self.time
     }
+    /// What the music sounds like this frame.
+    pub fn spectrum(&self) -> &Spectrum {
+        &self.spectrum
+    }
     pub fn new() -> Self {
         UpdateAccumulator {
             obstacles_to_add: vec![],
             events: vec![],
+            sfx: vec![],
+            filters: vec![],
+            stems: vec![],
+            stem_energy: vec![],
+            spectrum: Spectrum::default(),
+            react_bg: None,
+            react_float: None,
+            graze_margin: None,
+            camera_moves: vec![],
+            arena_moves: vec![],
+            punch: 0.0,
             jerk: Vec2::ZERO,
             bg: None,
             fg: None,
@@ -66,6 +104,14 @@ impl UpdateAccumulator {
             time: 0.0
         }
     }
+    /// Runs `spawn` as if it were beat `time`, so that obstacles it adds start then and get caught up to now.\
+    /// For obstacles spawning others on a schedule of their own, which rarely lines up with a frame either.
+    pub fn at<R>(&mut self, time: f32, spawn: impl FnOnce(&mut Self) -> R) -> R {
+        let now = std::mem::replace(&mut self.time, time);
+        let ret = spawn(self);
+        self.time = now;
+        ret
+    }
     pub fn obst(&mut self, obst: impl Obstacle) {
         self.obstacles_to_add.push(Obst::new(obst.box_clone(), self.time));
     }
@@ -78,6 +124,26 @@ impl UpdateAccumulator {
     pub fn shake(&mut self, shake: f32) {
         self.shake += shake;
     }
+    /// Eases the camera to look at `pos` (in world space) over `beats` beats.
+    pub fn camera_pos(&mut self, pos: Vec2, beats: f32, ease: impl Easing + 'static) {
+        self.camera_moves.push(CameraMove::new(CameraKey::Pos(pos), self.time, beats, Box::new(ease)));
+    }
+    /// Eases the camera's zoom to `zoom` over `beats` beats. 1 is normal.
+    pub fn camera_zoom(&mut self, zoom: f32, beats: f32, ease: impl Easing + 'static) {
+        self.camera_moves.push(CameraMove::new(CameraKey::Zoom(zoom), self.time, beats, Box::new(ease)));
+    }
+    /// Eases the camera's rotation to `rot` radians over `beats` beats, turning the world clockwise.
+    pub fn camera_rot(&mut self, rot: f32, beats: f32, ease: impl Easing + 'static) {
+        self.camera_moves.push(CameraMove::new(CameraKey::Rot(rot), self.time, beats, Box::new(ease)));
+    }
+    /// Eases the bounds the player is kept inside to `bounds` (in world space) over `beats` beats, see `Arena::inset`.
+    pub fn arena(&mut self, bounds: Rect, beats: f32, ease: impl Easing + 'static) {
+        self.arena_moves.push(ArenaMove::new(bounds, self.time, beats, Box::new(ease)));
+    }
+    /// Zooms in by `amount` (0.1 is 10%) for a moment, springing back on its own.
+    pub fn zoom_punch(&mut self, amount: f32) {
+        self.punch += amount;
+    }
     pub fn bg(&mut self, bg: Color) {
         self.bg = Some(bg);
     }
@@ -90,9 +156,56 @@ impl UpdateAccumulator {
     pub fn fg_raw(&mut self, fg: Box<dyn ColorEase>) {
         self.smi(ColorChange::fg(fg));
     }
+    /// Slows the level down (or speeds it up) to `scale` times its normal speed over `beats` beats. 1 goes back to normal.
+    pub fn speed(&mut self, scale: f32, beats: f32) {
+        self.smi(SpeedChange { scale, beats });
+    }
     pub fn float(&mut self, float: f32) {
         self.float = Some(float)
     }
+    pub fn sfx(&mut self, sfx: Sfx) {
+        self.sfx.push(sfx);
+    }
+    /// Gives sounds queued since the `from`th one `pos`, unless they already have a position.
+    fn locate_sfx(&mut self, from: usize, pos: Option<Vec2>) {
+        for sfx in &mut self.sfx[from..] {
+            sfx.pos = sfx.pos.or(pos);
+        }
+    }
+    /// Animates a music filter parameter to `to` over `beats` beats (instantly if `beats <= 0`).
+    pub fn filter(&mut self, param: FilterParam, to: f32, beats: f32) {
+        self.filters.push((param, to, beats));
+    }
+    /// Sweeps the music's low-pass cutoff from `from` to `to` Hz, e.g. to close in on a breakdown.\
+    /// Leaves the filter on; follow up with `filter(FilterParam::LowPassWet, 0.0, ...)` to release it.
+    pub fn lowpass_sweep(&mut self, from: f32, to: f32, beats: f32) {
+        self.filter(FilterParam::LowPassWet, 1.0, 0.0);
+        self.filter(FilterParam::LowPassFreq, from, 0.0);
+        self.filter(FilterParam::LowPassFreq, to, beats);
+    }
+    pub fn echo(&mut self, wet: f32, beats: f32) {
+        self.filter(FilterParam::EchoWet, wet, beats);
+    }
+    /// Fades stem `idx` of the level's music (see `GameState::stems`) to `volume` over `beats` beats.
+    pub fn stem(&mut self, idx: usize, volume: f32, beats: f32) {
+        self.stems.push((idx, volume, beats));
+    }
+    /// How loud stem `idx` is this frame, roughly 0-1. 0 for stems that don't exist.
+    pub fn stem_energy(&self, idx: usize) -> f32 {
+        *self.stem_energy.get(idx).unwrap_or(&0.0)
+    }
+    /// Makes the background flash towards the foreground color with the bass. 0 turns it off.
+    pub fn react_bg(&mut self, amount: f32) {
+        self.react_bg = Some(amount);
+    }
+    /// Adds bass-driven camera float on top of `float`. 0 turns it off.
+    pub fn react_float(&mut self, amount: f32) {
+        self.react_float = Some(amount);
+    }
+    /// How close the player has to pass obstacles to graze them, see `GRAZE_MARGIN`.
+    pub fn graze_margin(&mut self, margin: f32) {
+        self.graze_margin = Some(margin);
+    }
     pub fn sm(&mut self, modifier: Box<dyn StateModifier>) {
         self.events.push(modifier);
     }
@@ -163,9 +276,21 @@ pub struct LevelState {
     pub hits_left: usize,
     pub fg_color: Box<dyn ColorEase>,
     pub bg_color: Box<dyn ColorEase>,
-    pub cam_jerk: Vec2,
-    pub cam_shake: f32,
-    pub cam_float: f32,
+    pub camera: Camera,
+    /// The view the last frame was drawn through, for placing things on screen between draws.
+    pub view: View,
+    pub arena: Arena,
+    pub spectrum: Spectrum,
+    /// How much the bass flashes the background, see `UpdateAccumulator::react_bg`.
+    pub react_bg: f32,
+    pub graze_margin: f32,
+    pub grazes: usize,
+    pub score: u32,
+    pub hits_taken: usize,
+    /// 1 right after a graze, fading to 0.
+    pub graze_flash: f32,
+    /// Set when the player leaves with escape, so the level doesn't count as finished.
+    pub quit: bool,
 }
 impl LevelState {
     pub fn new() -> Self {
@@ -177,17 +302,60 @@ impl LevelState {
             hits_left: 3,
             fg_color: Box::new(|_|Color::new(1.0, 0.0, 0.5, 1.0)),
             bg_color: Box::new(|_|Color::new(0.0, 0.0, 0.0, 1.0)),
-            cam_jerk: Vec2::ZERO,
-            cam_shake: 0.0,
-            cam_float: 0.0,
+            camera: Camera::default(),
+            view: Camera::default().view(0.0, 0.0),
+            arena: Arena::default(),
+            spectrum: Spectrum::default(),
+            react_bg: 0.0,
+            graze_margin: GRAZE_MARGIN,
+            grazes: 0,
+            score: 0,
+            hits_taken: 0,
+            graze_flash: 0.0,
+            quit: false,
+        }
+    }
+    pub fn results(&self) -> Results {
+        Results { grazes: self.grazes, score: self.score, hits_taken: self.hits_taken }
+    }
+}
+
+/// How a finished level went, shown until clicked away.
+pub struct Results {
+    pub grazes: usize,
+    pub score: u32,
+    pub hits_taken: usize,
+}
+impl Results {
+    pub fn draw(&self) {
+        clear_virtual(BLACK);
+        let lines = [
+            ("Level complete".to_string(), 60),
+            (format!("Score: {}", self.score), 40),
+            (format!("Grazes: {}", self.grazes), 40),
+            (format!("Hits taken: {}", self.hits_taken), 40),
+            ("Click to continue".to_string(), 24),
+        ];
+        for (idx, (txt, size)) in lines.iter().enumerate() {
+            let dims = measure_text(txt, None, *size, 1.0);
+            let y = screen_height() / 2.0 + (idx as f32 - (lines.len() - 1) as f32 / 2.0) * 70.0;
+            draw_text(txt, (screen_width() - dims.width) / 2.0, y + dims.offset_y / 2.0, *size as f32, if idx == lines.len() - 1 { GRAY } else { WHITE });
         }
     }
 }
 pub struct GameState {
     pub state: EparState,
     pub mus: Music,
+    pub sfx: SfxCreator,
+    pub mixer: Mixer,
+    pub preview: Preview,
+    pub metronome: Metronome,
     pub bpm: f32,
-    pub wav: Wav
+    /// Streamed rather than decoded up front, so long songs start quickly and don't sit in memory.
+    pub wav: WavStream,
+    /// Stems for the next level to start, set by its loader.
+    pub stem_files: Vec<String>,
+    pub stem_wavs: Vec<WavStream>,
 }
 impl GameState {
     pub fn set_fg_color(&mut self, clr: Color) {
@@ -196,45 +364,99 @@ impl GameState {
     pub fn set_bg_color(&mut self, clr: Color) {
         self.state.map(|s|s.bg_color = Box::new(move|_|clr));
     }
-    pub fn new(mus: Music) -> Self {
+    pub fn new(mus: Music, sfx: SfxCreator, mixer: Mixer, preview: Preview) -> Self {
         GameState {
             bpm: 0.0,
             state: EparState::MainMenu,
             mus,
-            wav: Wav::default()
+            sfx,
+            mixer,
+            preview,
+            metronome: Metronome::default(),
+            wav: WavStream::default(),
+            stem_files: vec![],
+            stem_wavs: vec![],
         }
     }
     pub fn load_level(&mut self, lvl: EparLevel, start: f32, speed: f32) -> Result<(), Box<dyn Error>> {
         let state = LevelState::new();
-        self.wav = Wav::default();
-        let (offset, bpm, audiofile) = lvl.level()(self);
+        self.wav = WavStream::default();
+        let (offset, bpm, audiofile) = lvl.info();
+        lvl.level()(self)?;
+        self.start_music(audiofile, offset, bpm, start, speed)
+    }
+    pub fn load_chart(&mut self, chart: &Chart, start: f32, speed: f32) -> Result<(), Box<dyn Error>> {
+        self.wav = WavStream::default();
+        self.add_events(chart.events());
+        self.start_music(&chart.audio, chart.offset, chart.bpm, start, speed)
+    }
+    /// Endless mode on any song file; see `endless::endless`.
+    pub fn load_endless(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.wav = WavStream::default();
+        let (offset, bpm) = endless(self, path)?;
+        self.start_music(path, offset, bpm, 0.0, 1.0)?;
+        self.mus.set_looping(self.wav.length() as f32);
+        Ok(())
+    }
+    /// Plays a level's song with none of its events, to tap out a chart for it.\
+    /// The chart is saved to `charts/<level>.chart`.
+    pub fn record_level(&mut self, lvl: EparLevel) -> Result<(), Box<dyn Error>> {
+        self.wav = WavStream::default();
+        let (offset, bpm, audiofile) = lvl.info();
+        self.start_music(audiofile, offset, bpm, 0.0, 1.0)?;
+        self.state = EparState::Recording(Recorder::new(audiofile, bpm, offset, &format!("charts/{lvl:?}.chart")));
+        Ok(())
+    }
+    /// Call from a level loader to play these files along with the level's song, in sync with it.\
+    /// They're numbered in order for `UpdateAccumulator::stem`.
+    pub fn stems(&mut self, files: &[&str]) {
+        self.stem_files = files.iter().map(|f| f.to_string()).collect();
+    }
+    /// Shared tail of level loading, once the events are in.
+    fn start_music(&mut self, audiofile: &str, offset: f32, bpm: f32, start: f32, speed: f32) -> Result<(), Box<dyn Error>> {
         self.bpm = bpm;
         self.sort();
+        self.preview.stop(self.mixer.fade_time);
         self.wav.load(audiofile)?;
-        self.mus.replace(&self.wav, bpm, offset / speed);
+        self.mus.replace(&self.wav, bpm, offset);
+        self.stem_wavs.clear();
+        for file in std::mem::take(&mut self.stem_files) {
+            let mut stem = WavStream::default();
+            stem.load(&file)?;
+            self.mus.add_stem(&stem);
+            self.stem_wavs.push(stem);
+        }
+        self.mus.fade_in(self.mixer.fade_time);
         self.mus.speed(speed);
         self.snip(start + offset);
-        self.mus.seek(start / speed)?;
+        self.mus.seek(start)?;
+        self.mus.start();
         Ok(())
     }
     pub fn reset(&mut self) {
-        self.mus.stop();
+        self.mus.fade_out(self.mixer.fade_time);
+        self.mixer.reset_filters();
         self.state.map(|s| {
             s.fg_color = Box::new(|_|Color::new(1.0, 0.0, 0.5, 1.0));
             s.bg_color = Box::new(|_|Color::new(0.0, 0.0, 0.0, 1.0));
-            s.cam_float = 0.0;
-            s.cam_jerk = Vec2::ZERO;
-            s.cam_shake = 0.0;
+            s.camera = Camera::default();
+            s.arena = Arena::default();
+            s.react_bg = 0.0;
             s.hits_left = 3;
+            s.graze_margin = GRAZE_MARGIN;
+            s.grazes = 0;
+            s.score = 0;
+            s.hits_taken = 0;
+            s.graze_flash = 0.0;
             s.time = 0.0;
             s.events = vec![];
             s.obsts = vec![];
         });
         self.bpm = 0.0;
-        self.wav = Wav::default();
+        // `self.wav` is left alone; dropping it would cut off the fade out.
     }
     pub fn exit(&mut self) {
-        self.mus.stop();
+        self.mus.fade_out(self.mixer.fade_time);
         self.state = EparState::MainMenu;
     }
     pub fn add_event(&mut self, event: GSEvent) {
@@ -271,15 +493,20 @@ impl GameState {
         self.state.map(|s|s.events.clear());
     }
     pub fn update(&mut self, mus_time: f32, frame_time: f32) {
+        self.tick_metronome(mus_time);
         match &mut self.state {
             EparState::InGame(state) => {
                 if is_key_pressed(KeyCode::Escape) {
+                    state.quit = true;
                     self.reset();
                     return;
                 }
                 state.time = mus_time;
+                state.spectrum.update(self.mixer.fft(), self.mixer.music_volume(), frame_time);
                 let smargs = ModifyArgs::default();
                 let mut accum = UpdateAccumulator::new();
+                accum.spectrum = state.spectrum.clone();
+                accum.stem_energy = self.mus.stem_energies();
                 'event_calls: loop {
                     if state.events.is_empty() { break 'event_calls; }
                     let time = state.events[0].0;
@@ -300,47 +527,111 @@ impl GameState {
                 if state.player.isecs > 0.0 {
                     state.player.isecs -= frame_time;
                 }
+                state.graze_flash = (state.graze_flash - frame_time * 3.0).max(0.0);
+                // the walls move first, so the player is clamped to where they're drawn this frame
+                for mv in accum.arena_moves.drain(..) {
+                    state.arena.animate(mv);
+                }
+                state.arena.update(state.time);
+                state.player.prev_pos = state.player.pos;
                 if is_key_down(KeyCode::W) { state.player.pos.y -= state.player.pps * frame_time; }
                 if is_key_down(KeyCode::S) { state.player.pos.y += state.player.pps * frame_time; }
                 if is_key_down(KeyCode::A) { state.player.pos.x -= state.player.pps * frame_time; }
                 if is_key_down(KeyCode::D) { state.player.pos.x += state.player.pps * frame_time; }
-                if state.player.dash <= 0.0 && is_key_pressed(KeyCode::Space) { state.player.dash = 0.3; }
-                state.cam_jerk *= 0.8;
-                state.cam_shake *= 0.95;
+                state.player.pos = state.arena.clamp(state.player.pos, state.player.rad);
+                if state.player.dash <= 0.0 && is_key_pressed(KeyCode::Space) {
+                    state.player.dash = 0.3;
+                    accum.sfx(Sfx::player("dash"));
+                }
+                state.camera.settle();
         
                 accum.time = state.time;
         
+                let dt = frame_time / 60.0 * self.bpm * self.mus.get_speed();
                 let mut i = 0;
                 while i < state.obsts.len() {
                     let start = state.obsts[i].start_time;
-                    let dt = frame_time / 60.0 * self.bpm * self.mus.get_speed();
                     let t = state.time - start;
+                    let queued = accum.sfx.len();
                     state.obsts[i].obstacle.update(&mut accum, dt, t, dt, t);
+                    accum.locate_sfx(queued, state.obsts[i].obstacle.audio_pos());
                     i += 1;
                 }
-                for obst in &state.obsts {
-                    if state.player.dash <= 0.0 && state.player.isecs <= 0.0 && obst.obstacle.collides(state.player) {
+                let grid = Broadphase::build(BROADPHASE_CELL_SIZE, state.obsts.iter().map(|o| o.obstacle.bounds()));
+                let margin = state.graze_margin;
+                for idx in grid.query(segment_bounds(state.player.prev_pos, state.player.pos, (state.player.rad + margin) * 2.0)) {
+                    let obst = &mut state.obsts[idx];
+                    if state.player.dash > 0.0 || state.player.isecs > 0.0 { continue; }
+                    if obst.obstacle.collides_swept(state.player) {
+                        // no grazing something that already hit
+                        obst.grazed = true;
+                        state.hits_taken += 1;
                         state.player.isecs = 2.0;
+                        accum.sfx(Sfx::player("hit"));
+                        self.mixer.duck();
+                        self.mixer.muffle(state.player.isecs);
                         println!("hit {}", state.hits_left);
                         if state.hits_left > 0 {
                             state.hits_left -= 1;
                         }
+                    } else if !obst.grazed && obst.obstacle.grazes(state.player, margin) {
+                        obst.grazed = true;
+                        state.grazes += 1;
+                        state.score += GRAZE_POINTS;
+                        state.graze_flash = 1.0;
+                        accum.sfx(Sfx::player("graze").volume(0.4));
                     }
                 }
-                let mut idx = 0;
-                while idx < state.obsts.len() {
-                    if state.obsts[idx].marked_for_removal || state.obsts[idx].obstacle.should_kill() {
-                        state.obsts.swap_remove(idx).obstacle.kill(&mut accum);
-                    } else {
-                        idx += 1;
+                // Spawns are caught up before anything is killed, so one caught up past its end goes now instead of
+                // being drawn for another frame. What dying (or catching up) obstacles spawn gets the same in turn.
+                loop {
+                    let mut spawned = std::mem::take(&mut accum.obstacles_to_add);
+                    for obst in &mut spawned {
+                        obst.catch_up(&mut accum, state.time);
                     }
+                    state.obsts.append(&mut spawned);
+                    let mut idx = 0;
+                    while idx < state.obsts.len() {
+                        if state.obsts[idx].marked_for_removal || state.obsts[idx].obstacle.should_kill() {
+                            let mut obst = state.obsts.swap_remove(idx);
+                            let queued = accum.sfx.len();
+                            obst.obstacle.kill(&mut accum);
+                            accum.locate_sfx(queued, obst.obstacle.audio_pos());
+                        } else {
+                            idx += 1;
+                        }
+                    }
+                    if accum.obstacles_to_add.is_empty() { break; }
+                }
+                state.camera.jerk += accum.jerk;
+                state.camera.shake += accum.shake;
+                state.camera.punch += accum.punch;
+                for mv in accum.camera_moves {
+                    state.camera.animate(mv);
+                }
+                state.camera.update(state.time);
+                // anything obstacles asked for starts moving next frame
+                for mv in accum.arena_moves {
+                    state.arena.animate(mv);
                 }
-                state.obsts.append(&mut accum.obstacles_to_add);
-                state.cam_jerk += accum.jerk;
-                state.cam_shake += accum.shake;
                 if let Some(fg) = accum.fg { state.fg_color = Box::new(move |_|fg); }
                 if let Some(bg) = accum.bg { state.bg_color = Box::new(move |_|bg); }
-                if let Some(float) = accum.float { state.cam_float = float; }
+                if let Some(float) = accum.float { state.camera.float = float; }
+                if let Some(react) = accum.react_bg { state.react_bg = react; }
+                if let Some(react) = accum.react_float { state.camera.react_float = react; }
+                if let Some(margin) = accum.graze_margin { state.graze_margin = margin; }
+                // sounds are panned by where they are on screen, which the camera can move, zoom and turn away from world space
+                self.sfx.listener = state.view.to_screen(state.player.pos);
+                for mut sfx in accum.sfx {
+                    sfx.pos = sfx.pos.map(|pos| state.view.to_screen(pos));
+                    self.sfx.play(sfx);
+                }
+                for (param, to, beats) in accum.filters {
+                    self.mixer.fade_filter(param, to, beats * 60.0 / self.bpm / self.mus.get_speed());
+                }
+                for (idx, volume, beats) in accum.stems {
+                    self.mus.fade_stem(idx, volume, beats * 60.0 / self.bpm / self.mus.get_speed());
+                }
                 for i in accum.events {
                     i.run(self, smargs);
                 }
@@ -350,12 +641,13 @@ impl GameState {
     }
     pub fn draw(&mut self) {
         self.state.map(|s| {
-            let offset = s.cam_jerk
-                + vec2(gen_range(-s.cam_shake, s.cam_shake), gen_range(-s.cam_shake, s.cam_shake))
-                + vec2((s.time).sin(), (s.time * 1.2).sin()) * s.cam_float;
-            clear_background(s.bg_color.apply(s.time));
+            let view = s.camera.view(s.time, s.spectrum.bass);
+            s.view = view;
+            clear_virtual(mix(s.bg_color.apply(s.time), s.fg_color.apply(s.time), (s.spectrum.bass * s.react_bg).clamp(0.0, 1.0)));
+            set_camera(&view.camera);
+            s.arena.draw(s.fg_color.apply(s.time));
             for obst in &mut s.obsts {
-                obst.obstacle.draw(s.fg_color.apply(s.time), offset);
+                obst.obstacle.draw(s.fg_color.apply(s.time));
             }
             let color = match (s.player.isecs > 0.0, s.player.dash > 0.0) {
                 (false, false) => soft_pink(),
@@ -363,17 +655,24 @@ impl GameState {
                 (false, true) => dash_color(),
                 (true, true) => hitdash_color()
             };
-            draw_circle(s.player.pos.x + offset.x, s.player.pos.y + offset.y, s.player.rad, color);
-            let tpos = s.player.pos + offset + vec2(-s.player.rad, -s.player.rad * 2.0);
+            draw_circle(s.player.pos.x, s.player.pos.y, s.player.rad, color);
+            if s.graze_flash > 0.0 {
+                // a ring that flies out from the edge of the graze margin
+                let rad = s.player.rad + s.graze_margin * (2.0 - s.graze_flash);
+                draw_circle_lines(s.player.pos.x, s.player.pos.y, rad, 2.0, acmul(WHITE, s.graze_flash));
+            }
+            // UI from here on, which doesn't zoom or turn with the world
+            set_camera(&virtual_camera());
+            let tpos = view.to_screen(s.player.pos) + vec2(-s.player.rad, -s.player.rad * 2.0);
             draw_text(&format!("{}", s.hits_left), tpos.x, tpos.y, s.player.rad * 5.0, WHITE);
             if COLLISION_DBG {
+                let grid = Broadphase::build(BROADPHASE_CELL_SIZE, s.obsts.iter().map(|o| o.obstacle.bounds()));
+                // sampled in world space under each fragment of the screen, so it lines up with what's drawn
                 for x in (0..screen_width() as usize).step_by(COLLISION_FRAGMENT_SIZE) {
                     for y in (0..screen_height() as usize).step_by(COLLISION_FRAGMENT_SIZE) {
-                        for i in &s.obsts {
-                            if i.obstacle.collides(Player {
-                                pos: vec2(x as f32, y as f32),
-                                ..s.player
-                            }) {
+                        let pos = view.to_world(vec2(x as f32, y as f32));
+                        for i in grid.query(circle_bounds(pos, s.player.rad)).into_iter().map(|idx| &s.obsts[idx]) {
+                            if i.obstacle.collides(Player { pos, ..s.player }) {
                                 draw_rectangle(x as f32, y as f32, COLLISION_FRAGMENT_SIZE as f32, COLLISION_FRAGMENT_SIZE as f32, acmul(RED, 0.5));
                             }
                         }
@@ -381,6 +680,30 @@ impl GameState {
                 }
             }
         });
+        if let EparState::InGame(s) = &self.state {
+            self.draw_metronome(s.time);
+        }
+    }
+    /// Toggles the metronome with M, and clicks it if `beat` has moved on to a new beat.
+    pub fn tick_metronome(&mut self, beat: f32) {
+        if is_key_pressed(KeyCode::M) { self.metronome.enabled = !self.metronome.enabled; }
+        if let Some(click) = self.metronome.tick(beat) {
+            self.sfx.play(click);
+        }
+    }
+    /// A dot in the top right that pulses on every beat (blue on the bar), with the bar and beat next to it.\
+    /// If it's off from the music, the level's offset is.
+    pub fn draw_metronome(&self, beat: f32) {
+        if !self.metronome.enabled { return; }
+        let whole = beat.floor() as i64;
+        let per_bar = self.metronome.beats_per_bar as i64;
+        let pulse = sq(1.0 - (beat - beat.floor()));
+        let color = if self.metronome.is_accent(whole) { SKYBLUE } else { WHITE };
+        let pos = vec2(screen_width() - 40.0, 40.0);
+        draw_circle(pos.x, pos.y, 8.0 + 12.0 * pulse, acmul(color, 0.4 + 0.6 * pulse));
+        let txt = format!("{}.{} ({beat:.2})", whole.div_euclid(per_bar), whole.rem_euclid(per_bar) + 1);
+        let dims = measure_text(&txt, None, 24, 1.0);
+        draw_text(&txt, pos.x - 30.0 - dims.width, pos.y + dims.offset_y / 2.0, 24.0, WHITE);
     }
     pub fn add_obst(&mut self, obst: Obst) {
         self.state.map(|s|s.obsts.push(obst));
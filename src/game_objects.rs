@@ -1,11 +1,11 @@
 use std::f32::consts::TAU;
 
-use macroquad::{prelude::{Vec2, Rect, Color, WHITE, vec2}, shapes::{draw_circle, draw_line, draw_triangle}, window::{screen_height, screen_width}, rand::gen_range};
+use macroquad::{prelude::{Vec2, Rect, Color, WHITE, vec2}, shapes::{draw_circle, draw_line, draw_triangle}, rand::gen_range};
 use paste::paste;
 use perlin2d::PerlinNoise2D;
 use rand::{seq::SliceRandom, thread_rng};
 
-use crate::{utils::{sq, self, collide_cr, mix, draw_rrect, collide_cc, screen_center, acmul, circ_climb, adjust, screen_size, recip_ease, collide_circ_arc, draw_arc, cmul}, game::{Accumulatee, ModifyArgs, UpdateAccumulator}};
+use crate::{utils::{sq, self, screen_width, screen_height, collide_cr, mix, draw_rrect, collide_cc, screen_center, acmul, circ_climb, adjust, screen_size, recip_ease, collide_circ_arc, draw_arc, cmul, collide_capsule}, game::{Accumulatee, ModifyArgs, UpdateAccumulator}, sound::Sfx, broadphase::{EVERYWHERE, circle_bounds, segment_bounds}, sat::{self, Hitbox}, lifecycle::{Lifecycle, Phase}};
 
 use super::game::GameState;
 
@@ -26,11 +26,28 @@ macro_rules! vec2_builder {
 pub struct Obst {
     pub obstacle: Box<dyn Obstacle>,
     pub marked_for_removal: bool,
-    pub start_time: f32
+    pub start_time: f32,
+    /// Set once the player grazes this, so each obstacle only counts once.
+    pub grazed: bool,
 }
 impl Obst {
     pub fn new(obst: Box<dyn Obstacle>, start_time: f32) -> Self {
-        Obst { obstacle: obst, marked_for_removal: false, start_time }
+        Obst { obstacle: obst, marked_for_removal: false, start_time, grazed: false }
+    }
+    /// Events rarely land exactly on a frame, so a freshly spawned obstacle is advanced by however late it is.\
+    /// Without this, integrators like `Pellet` would lag behind by up to a frame, which shows at low frame rates.
+    pub fn catch_up(&mut self, to_add: &mut UpdateAccumulator, now: f32) {
+        let late = now - self.start_time;
+        if late.is_finite() && late > 0.0 {
+            self.obstacle.update(to_add, late, late, late, late);
+        }
+    }
+    /// Tests if two obstacles are touching, for obstacles that react to each other.
+    pub fn overlaps(&self, other: &Obst) -> bool {
+        match (self.obstacle.hitbox(), other.obstacle.hitbox()) {
+            (Some(a), Some(b)) => a.collides(&b),
+            _ => false,
+        }
     }
 }
 impl Clone for Obst {
@@ -42,14 +59,20 @@ impl Clone for Obst {
 #[derive(Clone, Copy)]
 pub struct Player {
     pub pos: Vec2,
+    /// Where the player was last frame, for swept collision.
+    pub prev_pos: Vec2,
     pub rad: f32,
     pub pps: f32,
     pub dash: f32,
     pub isecs: f32,
 }
+impl Player {
+    pub fn hitbox(&self) -> Hitbox { Hitbox::Circle(self.pos, self.rad) }
+}
 impl Default for Player {
     fn default() -> Self {
-        Player { pos: Vec2::new(screen_width() / 2.0, screen_height() / 2.0), rad: 5.0, pps: 100.0, dash: 0.0, isecs: 0.0 }
+        let pos = Vec2::new(screen_width() / 2.0, screen_height() / 2.0);
+        Player { pos, prev_pos: pos, rad: 5.0, pps: 100.0, dash: 0.0, isecs: 0.0 }
     }
 }
 pub trait Obstacle {
@@ -62,22 +85,49 @@ pub trait Obstacle {
     /// the timing must stay the same. Therefore, `dtime` and `time` are used for timing, while\
     /// `dease` and `ease` are used for movement.
     fn update(&mut self, to_add: &mut UpdateAccumulator, dtime: f32, time: f32, dease: f32, ease: f32);
-    fn draw(&self, color: Color, offset: Vec2);
+    /// Drawn in world space, through the level's camera, so it lines up with the hitbox.
+    fn draw(&self, color: Color);
     fn box_clone(&self) -> Box<dyn Obstacle>;
+    /// Obstacles that telegraph keep their timing in a `Lifecycle`, and should be harmless until it's `dangerous`.
     fn collides(&self, player: Player) -> bool;
+    /// Like `collides`, but for the whole path of the player since last frame, so fast movement can't skip through.\
+    /// By default this tests the current shape at steps along the player's path, which covers things that only grow.
+    /// Fast movers should override it to account for their own motion.
+    fn collides_swept(&self, player: Player) -> bool {
+        let steps = (player.prev_pos.distance(player.pos) / player.rad).ceil().clamp(1.0, MAX_SWEEP_STEPS);
+        (1..=steps as usize).any(|i| self.collides(Player { pos: player.prev_pos.lerp(player.pos, i as f32 / steps), ..player }))
+    }
+    /// Whether the player passed within `margin` of this since last frame, for grazing. Only asked when `collides_swept` is false.\
+    /// By default it's a swept test with a bigger player, which is exact for anything that collides with the player's radius properly.
+    fn grazes(&self, player: Player, margin: f32) -> bool {
+        self.collides_swept(Player { rad: player.rad + margin, ..player })
+    }
     fn should_kill(&mut self) -> bool;
     /// Called before dropping. Use to trigger behaviour on death (e.g. bombs).
     fn kill(&mut self, to_add: &mut UpdateAccumulator) {}
+    /// Where sounds this obstacle makes come from, for panning. Sounds it queues without a position of their own get this one.
+    fn audio_pos(&self) -> Option<Vec2> { None }
+    /// Box around everything this can collide with right now, used to skip `collides` for obstacles nowhere near the player.\
+    /// `None` if it can't collide at all right now. Defaults to everywhere, which is always correct but never skips anything.
+    fn bounds(&self) -> Option<Rect> { Some(EVERYWHERE) }
+    /// The exact shape this can hit with right now, for colliding with things other than a circular player.\
+    /// `None` if it can't collide right now, or its shape isn't convex.
+    fn hitbox(&self) -> Option<Hitbox> { None }
 }
+/// Most steps `Obstacle::collides_swept` takes along the player's path.
+pub const MAX_SWEEP_STEPS: f32 = 16.0;
+
 #[derive(Clone, Copy)]
 pub struct Pellet {
     pub pos: Vec2,
     pub vel: Vec2,
-    pub rad: f32
+    pub rad: f32,
+    /// How far the last update moved it.
+    pub last_move: Vec2,
 }
 impl Pellet {
     pub fn new(pos: Vec2, vel: Vec2, rad: f32) -> Self {
-        Pellet { pos, vel, rad }
+        Pellet { pos, vel, rad, last_move: Vec2::ZERO }
     }
 }
 impl Obstacle for Pellet {
@@ -85,22 +135,29 @@ impl Obstacle for Pellet {
     fn collides(&self, player: Player) -> bool {
         collide_cc(self.pos, self.rad, player.pos, player.rad)
     }
-    fn draw(&self, color: Color, offset: Vec2) {
-        draw_circle(self.pos.x + offset.x, self.pos.y + offset.y, self.rad, color);
+    /// Tests in the pellet's frame of reference, where it stands still and the player moves along a line.
+    fn collides_swept(&self, player: Player) -> bool {
+        collide_capsule(player.prev_pos + self.last_move, player.pos, player.rad, self.pos, self.rad)
+    }
+    fn bounds(&self) -> Option<Rect> { Some(segment_bounds(self.pos - self.last_move, self.pos, self.rad * 2.0)) }
+    fn hitbox(&self) -> Option<Hitbox> { Some(Hitbox::Circle(self.pos, self.rad)) }
+    fn draw(&self, color: Color) {
+        draw_circle(self.pos.x, self.pos.y, self.rad, color);
     }
     fn should_kill(&mut self) -> bool {
         !Rect::new(-self.rad, -self.rad, screen_width() + self.rad, screen_height() + self.rad).contains(self.pos)
     }
     fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
-        self.pos += self.vel * dease;
+        self.last_move = self.vel * dease;
+        self.pos += self.last_move;
     }
 }
 
 pub struct Bomb {
     pub start: Vec2,
     pub target: Vec2,
-    pub time: f32,
-    pub life: f32,
+    /// No warning; it starts out tiny, which is warning enough.
+    pub life: Lifecycle,
     pub pellets: usize,
     pub pellet_vel: f32,
     pub pellet_rad: f32,
@@ -113,8 +170,7 @@ impl Bomb {
         Bomb {
             start,
             target,
-            time: 0.0,
-            life: lifetime,
+            life: Lifecycle::new(0.0, lifetime),
             pellets,
             pellet_vel,
             pellet_rad,
@@ -124,14 +180,10 @@ impl Bomb {
         }
     }
     pub fn pellet_spawner(gs: &mut UpdateAccumulator, args: ModifyArgs) {
-        gs.obstacle(Obst::new(Box::new(Pellet {
-            pos: args.pos,
-            vel: args.vel,
-            rad: args.rad
-        }), args.time))
+        gs.obstacle(Obst::new(Box::new(Pellet::new(args.pos, args.vel, args.rad)), args.time))
     }
-    pub fn pos(&self, offset: Vec2) -> Vec2 {
-        (self.start - self.target) / (self.time * self.snappiness + 1.0) + self.target + offset
+    pub fn pos(&self) -> Vec2 {
+        (self.start - self.target) / (self.life.time * self.snappiness + 1.0) + self.target
     }
 }
 impl Clone for Bomb {
@@ -143,12 +195,13 @@ impl Clone for Bomb {
     }
 }
 impl Obstacle for Bomb {
-    fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) { self.time = time; }
-    fn draw(&self, color: Color, offset: Vec2) {
-        let pos = self.pos(offset);
-        let size = self.time * self.rad;
+    fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) { self.life.update(time); }
+    fn draw(&self, color: Color) {
+        let color = self.life.color(color);
+        let pos = self.pos();
+        let size = self.life.time * self.rad;
         draw_circle(pos.x, pos.y, size, color);
-        let rot = self.time * 3.0;
+        let rot = self.life.time * 3.0;
         let size_fac = 1.2;
         let c1 = vec2(rot.cos(), rot.sin()) * size * size_fac + pos;
         let c2 = vec2(-rot.sin(), rot.cos()) * size * size_fac + pos;
@@ -159,18 +212,24 @@ impl Obstacle for Bomb {
     }
     fn box_clone(&self) -> Box<dyn Obstacle> { Box::new(self.clone()) }
     fn collides(&self, player: Player) -> bool {
-        utils::collide_cc(self.pos(Vec2::ZERO), self.rad * self.time, player.pos, player.rad)
+        self.life.dangerous() && utils::collide_cc(self.pos(), self.rad * self.life.time, player.pos, player.rad)
     }
-    fn should_kill(&mut self) -> bool { self.time >= self.life }
+    fn bounds(&self) -> Option<Rect> { self.life.dangerous().then(|| circle_bounds(self.pos(), self.rad * self.life.time)) }
+    fn hitbox(&self) -> Option<Hitbox> { self.life.dangerous().then(|| Hitbox::Circle(self.pos(), self.rad * self.life.time)) }
+    fn should_kill(&mut self) -> bool { self.life.done() }
+    fn audio_pos(&self) -> Option<Vec2> { Some(self.pos()) }
     fn kill(&mut self, to_add: &mut UpdateAccumulator) {
-        let pos = self.pos(Vec2::ZERO);
-        for i in 0..self.pellets {
+        let pos = self.pos();
+        to_add.sfx(Sfx::obstacle("bomb"));
+        // it went off at the end of its life, which was most likely partway through the last frame
+        let at = to_add.time() - (self.life.time - self.life.total());
+        to_add.at(at, |to_add| for i in 0..self.pellets {
             let period = i as f32 / self.pellets as f32 * TAU;
-            self.spawner.run(to_add, ModifyArgs::new(to_add.time()).pos(pos).vel(Vec2 {
+            self.spawner.run(to_add, ModifyArgs::new(at).pos(pos).vel(Vec2 {
                 x: period.sin() * self.pellet_vel,
                 y: period.cos() * self.pellet_vel
             }).rad(self.pellet_rad));
-        }
+        });
     }
 }
 
@@ -179,14 +238,9 @@ pub struct GrowLaser {
     pub start: Vec2,
     pub end: Vec2,
     pub thickness: f32,
-    pub warning_time: f32,
-    pub show_time: f32,
-    pub current_time: f32,
-    pub grow_time: f32,
-    pub shown: bool,
+    /// Grows in while entering and shrinks while leaving.
+    pub life: Lifecycle,
     pub jerk: Vec2,
-    pub fade_in: f32,
-    pub fade_opacity: f32
 }
 impl GrowLaser {
     pub fn new(start: Vec2, end: Vec2, thickness: f32, warning_time: f32, show_time: f32, jerk: Vec2) -> Self {
@@ -194,49 +248,33 @@ impl GrowLaser {
             start,
             end,
             thickness,
-            warning_time,
-            show_time,
             jerk,
-            current_time: 0.0,
-            shown: false,
-            grow_time: 0.25,
-            fade_opacity: 0.5,
-            fade_in: warning_time
+            life: Lifecycle::new(warning_time, show_time).enter(0.25).leave(0.25),
         }
     }
-    builder!(fade_opacity: f32);
-    builder!(fade_in: f32);
-    builder!(grow_time: f32);
+    pub fn fade_opacity(mut self, fade_opacity: f32) -> Self { self.life.warning_opacity = fade_opacity; self }
+    pub fn fade_in(mut self, fade_in: f32) -> Self { self.life.fade_in = Some(fade_in); self }
+    /// Beats spent growing in, and again shrinking out.
+    pub fn grow_time(mut self, grow_time: f32) -> Self { self.life.enter = grow_time; self.life.leave = grow_time; self }
     /// Calculates smoothed thickness
     pub fn thick(&self) -> f32 {
-        let total_time = self.warning_time + self.show_time;
-        if (self.warning_time..=self.warning_time + self.grow_time).contains(&self.current_time) {
-            self.thickness * (self.current_time - self.warning_time) / self.grow_time
-        } else if self.current_time > total_time - self.grow_time {
-            self.thickness * (self.current_time - total_time) / -self.grow_time + 1.0
-        } else {
-            self.thickness
+        match self.life.phase() {
+            Phase::Warning => self.thickness,
+            _ => self.thickness * self.life.enter_progress() * (1.0 - self.life.leave_progress()),
         }
     }
 }
 impl Obstacle for GrowLaser {
     fn update(&mut self, accum: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
-        self.current_time = time;
-        if !self.shown && self.current_time >= self.warning_time {
+        self.life.update(time);
+        if self.life.just_activated() {
             accum.jerk(self.jerk);
-            self.shown = true;
         }
     }
 
-    fn draw(&self, mut color: Color, offset: Vec2) {
-        if self.current_time < self.warning_time {
-            if self.current_time < self.fade_in {
-                color.a = self.current_time / self.fade_in * self.fade_opacity;
-            } else {
-                color.a = self.fade_opacity;
-            }
-        }
-        draw_line(self.start.x + offset.x, self.start.y + offset.y, self.end.x + offset.x, self.end.y + offset.y, self.thick(), color);
+    fn draw(&self, color: Color) {
+        let color = self.life.color(color);
+        draw_line(self.start.x, self.start.y, self.end.x, self.end.y, self.thick(), color);
     }
 
     fn box_clone(&self) -> Box<dyn Obstacle> {
@@ -244,14 +282,25 @@ impl Obstacle for GrowLaser {
     }
 
     fn collides(&self, player: Player) -> bool {
-        self.current_time >= self.warning_time && {
+        self.life.dangerous() && {
             let (center, size, rot) = utils::rectify_line(self.start, self.end, self.thick());
             utils::collide_cr(center, size, rot, player.pos, player.rad)
         }
     }
 
+    fn bounds(&self) -> Option<Rect> {
+        self.life.dangerous().then(|| segment_bounds(self.start, self.end, self.thick().abs()))
+    }
+
+    fn hitbox(&self) -> Option<Hitbox> {
+        self.life.dangerous().then(|| {
+            let (center, size, rot) = utils::rectify_line(self.start, self.end, self.thick().abs());
+            Hitbox::rect(center, size, rot)
+        })
+    }
+
     fn should_kill(&mut self) -> bool {
-        self.current_time >= self.warning_time + self.show_time
+        self.life.done()
     }
 }
 
@@ -260,12 +309,10 @@ pub struct SlamLaser {
     pub start: Vec2,
     pub end: Vec2,
     pub thickness: f32,
-    pub warning_time: f32,
-    pub show_time: f32,
-    pub current_time: f32,
-    pub leave_time: f32,
+    /// Retracts while leaving.
+    pub life: Lifecycle,
+    /// How far it reaches while warning, 0-1.
     pub anticipation: f32,
-    pub shown: bool,
     pub jerk: Vec2,
     pub shake: f32,
 }
@@ -275,58 +322,42 @@ impl SlamLaser {
             start,
             end,
             thickness,
-            warning_time,
-            show_time,
             jerk,
             shake,
-            current_time: 0.0,
-            shown: false,
             anticipation,
-            leave_time: 2.0
-        }
-    }
-    /// Will flash and fade out from white for `self.grow_time` beats, this function calculates the mix.
-    pub fn color(&self, normal: Color) -> Color {
-        if (self.warning_time..=self.warning_time + 0.5).contains(&self.current_time) {
-            mix(WHITE, normal, (self.current_time - self.warning_time) / 0.5)
-        } else {
-            normal
+            life: Lifecycle::new(warning_time, show_time).enter(0.5).leave(2.0),
         }
     }
     pub fn leave_time(mut self, new_time: f32) -> Self {
-        self.leave_time = new_time;
+        self.life.leave = new_time;
         self
     }
     /// Calculates slam lerp factor (0-1)
     pub fn slam(&self) -> f32 {
-        let total = self.warning_time + self.show_time;
-        if self.current_time < self.warning_time {
-            self.current_time / self.warning_time * self.anticipation
-        } else if self.current_time > total - self.leave_time {
-            let exit_point = total - self.leave_time;
-            -sq((self.current_time - exit_point) / self.leave_time) + 1.0
-        } else {
-            1.0
+        match self.life.phase() {
+            Phase::Warning => self.life.warning_progress() * self.anticipation,
+            _ => 1.0 - sq(self.life.leave_progress()),
         }
     }
 }
 impl Obstacle for SlamLaser {
     fn update(&mut self, accum: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
-        self.current_time = time;
-        if !self.shown && self.current_time >= self.warning_time {
+        self.life.update(time);
+        if self.life.just_activated() {
             accum.jerk(self.jerk);
             accum.shake(self.shake);
-            self.shown = true;
+            accum.sfx(Sfx::obstacle("slam"));
         }
     }
 
-    fn draw(&self, mut color: Color, offset: Vec2) {
-        let mut color = self.color(color);
+    fn draw(&self, color: Color) {
         let end = self.start.lerp(self.end, self.slam());
-        draw_line(self.start.x + offset.x, self.start.y + offset.y, end.x + offset.x, end.y + offset.y, self.thickness, color);
-        if self.current_time < self.warning_time {
-            color.a = self.current_time / self.warning_time * 0.5;
-            draw_line(self.start.x + offset.x, self.start.y + offset.y, self.end.x + offset.x, self.end.y + offset.y, self.thickness, color);
+        if self.life.phase() == Phase::Warning {
+            // the anticipating stub is solid, over the faded telegraph of the whole thing
+            draw_line(self.start.x, self.start.y, self.end.x, self.end.y, self.thickness, self.life.color(color));
+            draw_line(self.start.x, self.start.y, end.x, end.y, self.thickness, color);
+        } else {
+            draw_line(self.start.x, self.start.y, end.x, end.y, self.thickness, self.life.color(color));
         }
     }
 
@@ -335,14 +366,29 @@ impl Obstacle for SlamLaser {
     }
 
     fn collides(&self, player: Player) -> bool {
-        self.current_time >= self.warning_time && {
+        self.life.dangerous() && {
             let (center, size, rot) = utils::rectify_line(self.start, self.start.lerp(self.end, self.slam()), self.thickness);
             utils::collide_cr(center, size, rot, player.pos, player.rad)
         }
     }
 
+    fn bounds(&self) -> Option<Rect> {
+        self.life.dangerous().then(|| segment_bounds(self.start, self.start.lerp(self.end, self.slam()), self.thickness))
+    }
+
+    fn hitbox(&self) -> Option<Hitbox> {
+        self.life.dangerous().then(|| {
+            let (center, size, rot) = utils::rectify_line(self.start, self.start.lerp(self.end, self.slam()), self.thickness);
+            Hitbox::rect(center, size, rot)
+        })
+    }
+
     fn should_kill(&mut self) -> bool {
-        self.current_time >= self.warning_time + self.show_time
+        self.life.done()
+    }
+
+    fn audio_pos(&self) -> Option<Vec2> {
+        Some(self.start.lerp(self.end, 0.5))
     }
 }
 
@@ -366,15 +412,7 @@ impl Periodic {
     pub fn rect_trail(rect_life: f32, warning_time: f32, grow_time: f32, positioner: impl Fn(usize) -> (Vec2, Vec2, f32) + Clone + 'static) -> Box<dyn Accumulatee> {
         Box::new(move |gs: &mut UpdateAccumulator, sm: ModifyArgs| {
             let (center, size, rot) = positioner(sm.step);
-            gs.obst(RotatableRect {
-                center,
-                size,
-                rot,
-                warning_time,
-                show_time: rect_life,
-                current_time: 0.0,
-                grow_time,
-            })
+            gs.obst(RotatableRect::new(center, size, rot, warning_time, rect_life, grow_time))
         })
     }
     pub fn linear(rect_life: f32, warning_time: f32, grow_time: f32, start: Vec2, delta: Vec2, scale: Vec2, rot: f32) -> Box<dyn Accumulatee> {
@@ -389,15 +427,19 @@ impl Obstacle for Periodic {
         })
     }
     fn collides(&self, player: Player) -> bool { false }
-    fn draw(&self, color: Color, offset: Vec2) { }
+    fn bounds(&self) -> Option<Rect> { None }
+    fn draw(&self, color: Color) { }
     fn should_kill(&mut self) -> bool {
         self.time_div >= self.max_steps
     }
     fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
         self.time_mod += beat_delta;
         while self.time_mod >= self.interval {
-            self.modifier.run(to_add, ModifyArgs::new(to_add.time()).step(self.time_div));
             self.time_mod -= self.interval;
+            // this step was due `time_mod` beats ago
+            let at = to_add.time() - self.time_mod;
+            let (modifier, step) = (&self.modifier, self.time_div);
+            to_add.at(at, |to_add| modifier.run(to_add, ModifyArgs::new(at).step(step)));
             self.time_div += 1;
         }
     }
@@ -408,30 +450,21 @@ pub struct RotatableRect {
     pub center: Vec2,
     pub size: Vec2,
     pub rot: f32,
-    pub warning_time: f32,
-    pub show_time: f32,
-    pub current_time: f32,
-    pub grow_time: f32,
+    /// Pops in oversized while entering, and shrinks away while leaving.
+    pub life: Lifecycle,
 }
 impl RotatableRect {
+    /// `grow_time` is how long it takes to pop in, and to shrink away.
+    pub fn new(center: Vec2, size: Vec2, rot: f32, warning_time: f32, show_time: f32, grow_time: f32) -> Self {
+        RotatableRect { center, size, rot, life: Lifecycle::new(warning_time, show_time).enter(grow_time).leave(grow_time) }
+    }
     /// Calculates the animated size\
     /// `allow_oversize` specifies whether or not the size can overshoot `self.size`.
     pub fn size(&self, allow_oversize: bool) -> Vec2 {
-        let total_time = self.warning_time + self.show_time;
-        if allow_oversize && (self.warning_time..=self.warning_time + self.grow_time).contains(&self.current_time) {
-            self.size * ((self.current_time - self.warning_time) / -self.grow_time + 2.0)
-        } else if self.current_time >= total_time - self.grow_time {
-            self.size * ((self.current_time - total_time - self.grow_time) / -self.grow_time - 1.0)
+        if allow_oversize && self.life.dangerous() && self.life.enter_progress() < 1.0 {
+            self.size * (2.0 - self.life.enter_progress())
         } else {
-            self.size
-        }
-    }
-    /// Will flash and fade out from white for `self.grow_time` beats, this function calculates the mix.
-    pub fn color(&self, normal: Color) -> Color {
-        if (self.warning_time..=self.warning_time + self.grow_time).contains(&self.current_time) {
-            mix(WHITE, normal, (self.current_time - self.warning_time) / self.grow_time)
-        } else {
-            normal
+            self.size * (1.0 - self.life.leave_progress())
         }
     }
 }
@@ -440,20 +473,23 @@ impl Obstacle for RotatableRect {
         Box::new(self.clone())
     }
     fn collides(&self, player: Player) -> bool {
-        self.current_time >= self.warning_time && collide_cr(self.center, self.size(false), self.rot, player.pos, player.rad)
+        self.life.dangerous() && collide_cr(self.center, self.size(false), self.rot, player.pos, player.rad)
     }
-    fn draw(&self, mut color: Color, offset: Vec2) {
-        color = self.color(color);
-        if self.current_time < self.warning_time {
-            color.a = self.current_time / self.warning_time * 0.5;
-        }
-        draw_rrect(self.center + offset, self.size(true), self.rot, color)
+    fn bounds(&self) -> Option<Rect> {
+        // any rotation fits in the circle through the corners
+        self.life.dangerous().then(|| circle_bounds(self.center, self.size(false).length()))
+    }
+    fn hitbox(&self) -> Option<Hitbox> {
+        self.life.dangerous().then(|| Hitbox::rect(self.center, self.size(false), self.rot))
+    }
+    fn draw(&self, color: Color) {
+        draw_rrect(self.center, self.size(true), self.rot, self.life.color(color))
     }
     fn should_kill(&mut self) -> bool {
-        self.current_time >= self.show_time + self.warning_time
+        self.life.done()
     }
     fn update(&mut self, game_state: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
-        self.current_time = time;
+        self.life.update(time);
     }
 }
 
@@ -462,11 +498,9 @@ pub struct RotatingRect {
     pub center: Vec2,
     pub size: Vec2,
     pub rot: f32,
-    pub warning_time: f32,
-    pub show_time: f32,
-    pub current_time: f32,
+    /// Flashes while entering, and shrinks away while leaving.
+    pub life: Lifecycle,
     pub ease_time: f32,
-    pub grow_time: f32,
     pub rpb: f32,
 }
 impl Default for RotatingRect {
@@ -475,11 +509,8 @@ impl Default for RotatingRect {
             center: screen_center(),
             size: Vec2::ZERO,
             rot: 0.0,
-            warning_time: 8.0,
-            show_time: 0.0,
-            current_time: 0.0,
+            life: Lifecycle::new(8.0, 0.0).enter(0.25).leave(0.25),
             ease_time: 0.0,
-            grow_time: 0.25,
             rpb: 0.25
         }
     }
@@ -488,52 +519,134 @@ impl RotatingRect {
     builder!(center: Vec2);
     builder!(size: Vec2);
     builder!(rot: f32);
-    builder!(warning_time: f32);
-    builder!(show_time: f32);
-    builder!(grow_time: f32);
     builder!(rpb: f32);
-    /// Calculates the animated size\
-    /// `allow_oversize` specifies whether or not the size can overshoot `self.size`.
+    pub fn warning_time(mut self, warning_time: f32) -> Self { self.life.warning = warning_time; self }
+    pub fn show_time(mut self, show_time: f32) -> Self { self.life.show = show_time; self }
+    pub fn grow_time(mut self, grow_time: f32) -> Self { self.life.enter = grow_time; self.life.leave = grow_time; self }
+    /// Calculates the animated size
     pub fn get_size(&self) -> Vec2 {
-        let total_time = self.warning_time + self.show_time;
-        if self.current_time >= total_time - self.grow_time {
-            self.size * ((self.current_time - total_time - self.grow_time) / -self.grow_time - 1.0)
-        } else {
-            self.size
+        self.size * (1.0 - self.life.leave_progress())
+    }
+    /// Starts showing at `rot` radians, spins at `rps` revolutions per second.
+    pub fn get_rot(&self) -> f32 {
+        self.rot + (self.ease_time - self.life.warning) * self.rpb * TAU
+    }
+}
+impl Obstacle for RotatingRect {
+    fn box_clone(&self) -> Box<dyn Obstacle> {
+        Box::new(self.clone())
+    }
+    fn collides(&self, player: Player) -> bool {
+        self.life.dangerous() && collide_cr(self.center, self.get_size(), -self.get_rot(), player.pos, player.rad)
+    }
+    fn bounds(&self) -> Option<Rect> {
+        self.life.dangerous().then(|| circle_bounds(self.center, self.get_size().length()))
+    }
+    fn hitbox(&self) -> Option<Hitbox> {
+        self.life.dangerous().then(|| Hitbox::rect(self.center, self.get_size(), -self.get_rot()))
+    }
+    fn draw(&self, color: Color) {
+        draw_rrect(self.center, self.get_size(), self.get_rot(), self.life.color(color))
+    }
+    fn should_kill(&mut self) -> bool {
+        self.life.done()
+    }
+    fn update(&mut self, game_state: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
+        self.life.update(time);
+        self.ease_time = ease;
+    }
+}
+
+/// Any shape from a list of points around (0, 0), convex or not. Shows and hides like `RotatableRect`.
+#[derive(Clone)]
+pub struct Polygon {
+    /// In either winding order. Can be concave, but shouldn't cross itself.
+    pub points: Vec<Vec2>,
+    /// Indices into `points`, worked out once since the shape never changes.
+    triangles: Vec<[usize; 3]>,
+    convex: bool,
+    pub center: Vec2,
+    pub scale: Vec2,
+    pub rot: f32,
+    pub rpb: f32,
+    pub life: Lifecycle,
+    pub ease_time: f32,
+}
+impl Polygon {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Polygon {
+            triangles: sat::triangulate(&points),
+            convex: sat::is_convex(&points),
+            points,
+            center: screen_center(),
+            scale: Vec2::ONE,
+            rot: 0.0,
+            rpb: 0.0,
+            life: Lifecycle::new(1.0, 1.0).enter(0.25).leave(0.25),
+            ease_time: 0.0,
         }
     }
-    /// Will flash and fade out from white for `self.grow_time` beats, this function calculates the mix.
-    pub fn color(&self, normal: Color) -> Color {
-        if (self.warning_time..=self.warning_time + self.grow_time).contains(&self.current_time) {
-            mix(WHITE, normal, (self.current_time - self.warning_time) / self.grow_time)
+    /// A regular polygon with `sides` corners `rad` away from the center, the first pointing right.
+    pub fn regular(sides: usize, rad: f32) -> Self {
+        Self::new((0..sides).map(|i| vec2((i as f32 / sides as f32 * TAU).cos(), (i as f32 / sides as f32 * TAU).sin()) * rad).collect())
+    }
+    /// A star with `points` points, alternating between `outer` and `inner` away from the center.
+    pub fn star(points: usize, outer: f32, inner: f32) -> Self {
+        Self::new((0..points * 2).map(|i| {
+            let angle = i as f32 / (points * 2) as f32 * TAU;
+            vec2(angle.cos(), angle.sin()) * if i % 2 == 0 { outer } else { inner }
+        }).collect())
+    }
+    builder!(center: Vec2);
+    builder!(scale: Vec2);
+    builder!(rot: f32);
+    builder!(rpb: f32);
+    pub fn warning_time(mut self, warning_time: f32) -> Self { self.life.warning = warning_time; self }
+    pub fn show_time(mut self, show_time: f32) -> Self { self.life.show = show_time; self }
+    pub fn grow_time(mut self, grow_time: f32) -> Self { self.life.enter = grow_time; self.life.leave = grow_time; self }
+    /// Calculates the animated scale, like `RotatableRect::size`.
+    pub fn get_scale(&self, allow_oversize: bool) -> Vec2 {
+        if allow_oversize && self.life.dangerous() && self.life.enter_progress() < 1.0 {
+            self.scale * (2.0 - self.life.enter_progress())
         } else {
-            normal
+            self.scale * (1.0 - self.life.leave_progress())
         }
     }
-    /// Starts showing at `rot` radians, spins at `rps` revolutions per second.
+    /// Starts showing at `rot` radians, spins at `rpb` revolutions per beat.
     pub fn get_rot(&self) -> f32 {
-        self.rot + (self.ease_time - self.warning_time) * self.rpb * TAU
+        self.rot + (self.ease_time - self.life.warning).max(0.0) * self.rpb * TAU
+    }
+    /// Points where they are right now.
+    pub fn world_points(&self, allow_oversize: bool) -> Vec<Vec2> {
+        sat::transform(&self.points, self.center, self.get_scale(allow_oversize), self.get_rot())
     }
 }
-impl Obstacle for RotatingRect {
+impl Obstacle for Polygon {
     fn box_clone(&self) -> Box<dyn Obstacle> {
         Box::new(self.clone())
     }
     fn collides(&self, player: Player) -> bool {
-        self.current_time >= self.warning_time && collide_cr(self.center, self.get_size(), -self.get_rot(), player.pos, player.rad)
+        self.life.dangerous() && sat::collide_polygon_circle(&self.world_points(false), player.pos, player.rad)
+    }
+    fn bounds(&self) -> Option<Rect> {
+        let reach = self.points.iter().map(|p| p.length()).fold(0.0, f32::max) * self.get_scale(false).abs().max_element();
+        self.life.dangerous().then(|| circle_bounds(self.center, reach))
     }
-    fn draw(&self, mut color: Color, offset: Vec2) {
-        color = self.color(color);
-        if self.current_time < self.warning_time {
-            color.a = self.current_time / self.warning_time * 0.5;
+    fn hitbox(&self) -> Option<Hitbox> {
+        (self.life.dangerous() && self.convex).then(|| Hitbox::Polygon(self.world_points(false)))
+    }
+    fn draw(&self, color: Color) {
+        let color = self.life.color(color);
+        let points = self.world_points(true);
+        for [a, b, c] in &self.triangles {
+            draw_triangle(points[*a], points[*b], points[*c], color);
         }
-        draw_rrect(self.center + offset, self.get_size(), self.get_rot(), color)
     }
     fn should_kill(&mut self) -> bool {
-        self.current_time >= self.show_time + self.warning_time
+        self.life.done()
     }
-    fn update(&mut self, game_state: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
-        self.current_time = time;
+    fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
+        self.life.update(time);
         self.ease_time = ease;
     }
 }
@@ -555,13 +668,15 @@ pub struct PelletSpinner {
 }
 impl PelletSpinner {
     pub fn run(&mut self, time: f32, cur_pos: Vec2, cur_rad: f32, to_add: &mut UpdateAccumulator) -> bool {
-        if time >= self.start_time + self.period * self.count as f32 && self.count < self.max {
+        let due = self.start_time + self.period * self.count as f32;
+        if time >= due && self.count < self.max {
             self.count += 1;
             let circ = vec2(
                 ((self.count as f32 / self.max as f32 + self.phase) * TAU).cos(),
                 ((self.count as f32 / self.max as f32 + self.phase) * TAU).sin(),
             );
-            to_add.obst(Pellet::new(cur_pos + circ * (cur_rad - self.rad), circ * self.speed, self.rad))
+            let pellet = Pellet::new(cur_pos + circ * (cur_rad - self.rad), circ * self.speed, self.rad);
+            to_add.at(to_add.time() - (time - due), |to_add| to_add.obst(pellet));
         }
         self.count >= self.max
     }
@@ -572,13 +687,12 @@ pub struct CenterProj {
     disp_amp: f32,
     disp_freq: Vec2,
     disp_phase: Vec2,
-    time: f32,
+    /// Shrinks away while leaving.
+    life: Lifecycle,
     ease: f32,
     rad: f32,
     pulse: f32,
-    warning_time: f32,
-    show_time: f32,
-    leave_time: f32,
+    bass_pulse: f32,
     pub events: Vec<(f32, CenterEvent)>,
     pellet_spinners: Vec<PelletSpinner>
 }
@@ -587,13 +701,11 @@ impl Default for CenterProj {
         CenterProj {
             disp_amp: 75.0,
             disp_freq: Vec2::ONE,
-            time: 0.0,
+            life: Lifecycle::new(1.0, 32.0).leave(0.25),
             ease: 0.0,
             rad: 20.0,
             pulse: 0.0,
-            warning_time: 1.0,
-            show_time: 32.0,
-            leave_time: 0.25,
+            bass_pulse: 0.0,
             events: vec![],
             disp_phase: Vec2::ZERO,
             pellet_spinners: vec![]
@@ -622,28 +734,25 @@ impl CenterProj {
     pub fn disp_freq_f32(mut self, val: f32) -> Self { self.disp_freq = vec2(val, val); self }
     builder!(disp_phase: Vec2);
     pub fn disp_phase_f32(mut self, val: f32) -> Self { self.disp_phase = vec2(val, val); self }
-    builder!(leave_time: f32);
-    builder!(warning_time: f32);
-    builder!(show_time: f32);
+    pub fn leave_time(mut self, leave_time: f32) -> Self { self.life.leave = leave_time; self }
+    pub fn warning_time(mut self, warning_time: f32) -> Self { self.life.warning = warning_time; self }
+    pub fn show_time(mut self, show_time: f32) -> Self { self.life.show = show_time; self }
+    /// Pulses along with the bass of the music on top of scripted pulses.
+    builder!(bass_pulse: f32);
     pub fn evs(mut self, mut events: impl IntoIterator<Item = (f32, CenterEvent)>) -> Self {
         for i in events.into_iter() {
             self.events.push(i);
         }
         self
     }
-    pub fn color(&self, color: Color, time: f32) -> Color {
-        if time < self.warning_time {
-            acmul(color, self.time / self.warning_time * 0.5)
-        } else {
-            mix(color, WHITE, self.pulse)
+    pub fn color(&self, color: Color) -> Color {
+        match self.life.phase() {
+            Phase::Warning => self.life.color(color),
+            _ => mix(color, WHITE, self.pulse),
         }
     }
-    pub fn size(&self, time: f32) -> f32 {
-        self.rad * (self.pulse + 1.0) * if self.time - self.warning_time > self.show_time - self.leave_time {
-            (self.warning_time + self.show_time - self.time) / self.leave_time
-        } else {
-            1.0
-        }
+    pub fn size(&self) -> f32 {
+        self.rad * (self.pulse + 1.0) * (1.0 - self.life.leave_progress())
     }
     pub fn sort(mut self) -> Self {
         self.events.sort_by(|(a, _), (b, _)|a.total_cmp(b));
@@ -654,9 +763,10 @@ impl CenterProj {
             CenterEvent::Pulse => {
                 self.pulse = 1.0;
                 to_add.shake(10.0);
+                to_add.sfx(Sfx::obstacle("pulse").volume(0.5));
             },
             CenterEvent::Lasers(count, phase) => {
-                let start = self.trackpos(self.time + 1.0);
+                let start = self.trackpos(self.life.time + 1.0);
                 for i in 0..count {
                     to_add.obst(SlamLaser::new(start, start + vec2(
                         ((i as f32 / count as f32 + phase) * TAU).cos(),
@@ -665,7 +775,7 @@ impl CenterProj {
                 }
             },
             CenterEvent::Pellets(count, speed, rad, phase, is_strong) => {
-                let start = self.trackpos(self.time);
+                let start = self.trackpos(self.life.time);
                 for i in 0..count {
                     let circ = vec2(
                         ((i as f32 / count as f32 + phase) * TAU).cos(),
@@ -687,7 +797,7 @@ impl CenterProj {
                     max: count,
                     phase,
                     period: 1.0 / ppb,
-                    start_time: self.time,
+                    start_time: self.life.time,
                     rad,
                     speed
                 })
@@ -695,9 +805,10 @@ impl CenterProj {
             CenterEvent::SPulse(strength) => {
                 self.pulse = 1.0;
                 to_add.shake(strength);
+                to_add.sfx(Sfx::obstacle("pulse").volume((strength / 20.0).min(1.5) * 0.5));
             },
             CenterEvent::MessyPellets(count, rad, min_speed, max_speed) => {
-                let pos = self.trackpos(self.time);
+                let pos = self.trackpos(self.life.time);
                 for i in 0..count {
                     let speed = gen_range(min_speed, max_speed);
                     let period = gen_range(0.0, TAU);
@@ -710,12 +821,14 @@ impl CenterProj {
 }
 impl Obstacle for CenterProj {
     fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
-        self.time = time;
+        self.life.update(time);
         self.ease = ease;
-        self.pulse *= 0.975;
+        self.pulse = (self.pulse * 0.975).max(to_add.spectrum().bass * self.bass_pulse);
         while self.events.len() > 0 {
-            if self.time - self.warning_time >= self.events[0].0 {
-                self.employ(self.events[0].1, to_add);
+            let (due, event) = self.events[0];
+            let late = self.life.time - self.life.warning - due;
+            if late >= 0.0 {
+                to_add.at(to_add.time() - late, |to_add| self.employ(event, to_add));
                 self.events.remove(0);
             } else {
                 break;
@@ -724,23 +837,64 @@ impl Obstacle for CenterProj {
         let mut i = 0;
         let pos = self.trackpos(self.ease);
         while i < self.pellet_spinners.len() {
-            if self.pellet_spinners[i].run(self.time, pos, self.rad, to_add) {
+            if self.pellet_spinners[i].run(self.life.time, pos, self.rad, to_add) {
                 self.pellet_spinners.remove(i);
             } else {
                 i += 1;
             }
         }
     }
-    fn draw(&self, color: Color, offset: Vec2) {
-        let pos = self.trackpos(self.ease) + offset;
-        draw_circle(pos.x, pos.y, self.size(self.time), self.color(color, self.time));
+    fn draw(&self, color: Color) {
+        let pos = self.trackpos(self.ease);
+        draw_circle(pos.x, pos.y, self.size(), self.color(color));
     }
     fn box_clone(&self) -> Box<dyn Obstacle> { Box::new(self.clone()) }
-    fn collides(&self, player: Player) -> bool { collide_cc(self.trackpos(self.ease), self.size(self.time), player.pos, player.rad) }
+    fn collides(&self, player: Player) -> bool { self.life.dangerous() && collide_cc(self.trackpos(self.ease), self.size(), player.pos, player.rad) }
+    fn audio_pos(&self) -> Option<Vec2> { Some(self.trackpos(self.ease)) }
+    fn bounds(&self) -> Option<Rect> { self.life.dangerous().then(|| circle_bounds(self.trackpos(self.ease), self.size().abs())) }
+    fn hitbox(&self) -> Option<Hitbox> { self.life.dangerous().then(|| Hitbox::Circle(self.trackpos(self.ease), self.size().abs())) }
     fn should_kill(&mut self) -> bool {
-        self.time > self.warning_time + self.show_time
+        self.life.done()
+    }
+}
+/// Runs `spawner` whenever the music has an onset (see `Spectrum::onset`), for `life` beats.\
+/// Useful for sections that should follow the song without hand-timing every hit.
+pub struct OnOnset {
+    pub spawner: Box<dyn Accumulatee>,
+    pub life: f32,
+    /// Minimum beats between two spawns.
+    pub cooldown: f32,
+    time: f32,
+    last: f32,
+    steps: usize,
+}
+impl OnOnset {
+    pub fn new(life: f32, spawner: impl Accumulatee + 'static) -> Self {
+        OnOnset { spawner: Box::new(spawner), life, cooldown: 0.25, time: 0.0, last: f32::NEG_INFINITY, steps: 0 }
+    }
+    builder!(cooldown: f32);
+}
+impl Clone for OnOnset {
+    fn clone(&self) -> Self {
+        OnOnset { spawner: self.spawner.box_clone(), ..*self }
+    }
+}
+impl Obstacle for OnOnset {
+    fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, time: f32, dease: f32, ease: f32) {
+        self.time = time;
+        if to_add.spectrum().onset && self.time - self.last >= self.cooldown {
+            self.last = self.time;
+            self.spawner.run(to_add, ModifyArgs::new(to_add.time()).step(self.steps));
+            self.steps += 1;
+        }
     }
+    fn draw(&self, color: Color) { }
+    fn box_clone(&self) -> Box<dyn Obstacle> { Box::new(self.clone()) }
+    fn collides(&self, player: Player) -> bool { false }
+    fn bounds(&self) -> Option<Rect> { None }
+    fn should_kill(&mut self) -> bool { self.time >= self.life }
 }
+
 #[derive(Clone, Copy)]
 pub enum CenterEvent {
     Pulse,
@@ -861,23 +1015,17 @@ impl Obstacle for GOLGrid {
             for x in 0..self.width {
                 for y in 0..self.height {
                     if self.get(x as isize, y as isize) {
-                        to_add.obst(RotatableRect {
-                            center: vec2(x as f32, y as f32) * pfac + pfac / 2.0,
-                            size: pfac,
-                            rot: 0.0,
-                            warning_time: if first { self.first_warning_time } else { self.warning_time },
-                            show_time: self.period * 1.25,
-                            current_time: 0.0,
-                            grow_time: self.period / 4.0,
-                        })
+                        let warning_time = if first { self.first_warning_time } else { self.warning_time };
+                        to_add.obst(RotatableRect::new(vec2(x as f32, y as f32) * pfac + pfac / 2.0, pfac, 0.0, warning_time, self.period * 1.25, self.period / 4.0))
                     }
                 }
             }
         }
     }
-    fn draw(&self, color: Color, offset: Vec2) { }
+    fn draw(&self, color: Color) { }
     fn box_clone(&self) -> Box<dyn Obstacle> { Box::new(self.clone()) }
     fn collides(&self, player: Player) -> bool { false }
+    fn bounds(&self) -> Option<Rect> { None }
     fn should_kill(&mut self) -> bool { self.ticks >= self.max }
 }
 
@@ -935,9 +1083,13 @@ impl Clone for Ease {
 impl Obstacle for Ease {
     fn box_clone(&self) -> Box<dyn Obstacle> { Box::new(self.clone()) }
     fn collides(&self, player: Player) -> bool { self.proj.collides(player) }
-    fn draw(&self, color: Color, offset: Vec2) { self.proj.draw(color, offset) }
+    fn collides_swept(&self, player: Player) -> bool { self.proj.collides_swept(player) }
+    fn draw(&self, color: Color) { self.proj.draw(color) }
     fn kill(&mut self, to_add: &mut UpdateAccumulator) { self.proj.kill(to_add) }
     fn should_kill(&mut self) -> bool { self.proj.should_kill() }
+    fn audio_pos(&self) -> Option<Vec2> { self.proj.audio_pos() }
+    fn bounds(&self) -> Option<Rect> { self.proj.bounds() }
+    fn hitbox(&self) -> Option<Hitbox> { self.proj.hitbox() }
     fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, relative_time: f32, dease: f32, ease: f32) {
         let time = self.ease.run(ease);
         let de = time - self.prev;
@@ -954,45 +1106,34 @@ pub struct SpinningArc {
     pub left_angle: f32,
     pub right_angle: f32,
     pub rpb: f32,
-    pub warning_time: f32,
-    pub show_time: f32,
+    /// Flashes for a beat while entering.
+    pub life: Lifecycle,
     pub ease: f32,
-
-    pub time: f32,
 }
 impl SpinningArc {
     pub fn new() -> Self {
-        Self::default()
+        SpinningArc { life: Lifecycle::default().enter(1.0), ..Self::default() }
     }
     pub fn rot(&self) -> f32 {
         self.ease * self.rpb * TAU
     }
-    pub fn color(&self, color: Color) -> Color {
-        if self.time < self.warning_time {
-            cmul(color, self.time / self.warning_time)
-        } else if (0.0..1.0).contains(&(self.time - self.warning_time)) {
-            mix(WHITE, color, self.time - self.warning_time)
-        } else {
-            color
-        }
-    }
     builder!(center: Vec2);
     builder!(inner_rad: f32);
     builder!(outer_rad: f32);
     builder!(left_angle: f32);
     builder!(right_angle: f32);
     builder!(rpb: f32);
-    builder!(warning_time: f32);
-    builder!(show_time: f32);
+    pub fn warning_time(mut self, warning_time: f32) -> Self { self.life.warning = warning_time; self }
+    pub fn show_time(mut self, show_time: f32) -> Self { self.life.show = show_time; self }
 }
 impl Obstacle for SpinningArc {
     fn update(&mut self, to_add: &mut UpdateAccumulator, beat_delta: f32, relative_time: f32, dease: f32, ease: f32) {
-        self.time = relative_time;
+        self.life.update(relative_time);
         self.ease = ease;
     }
 
-    fn draw(&self, color: Color, offset: Vec2) {
-        draw_arc(self.center + offset, self.inner_rad, self.outer_rad, self.left_angle + self.rot(), self.right_angle + self.rot(), 32, self.color(color))
+    fn draw(&self, color: Color) {
+        draw_arc(self.center, self.inner_rad, self.outer_rad, self.left_angle + self.rot(), self.right_angle + self.rot(), 32, self.life.color(color))
     }
 
     fn box_clone(&self) -> Box<dyn Obstacle> {
@@ -1000,10 +1141,14 @@ impl Obstacle for SpinningArc {
     }
 
     fn collides(&self, player: Player) -> bool {
-        collide_circ_arc(player.pos, player.rad, self.center, self.outer_rad, self.inner_rad, -self.rot(), self.right_angle - self.rot() - self.left_angle) && self.time >= self.warning_time
+        collide_circ_arc(player.pos, player.rad, self.center, self.outer_rad, self.inner_rad, -self.rot(), self.right_angle - self.rot() - self.left_angle) && self.life.dangerous()
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        self.life.dangerous().then(|| circle_bounds(self.center, self.outer_rad))
     }
 
     fn should_kill(&mut self) -> bool {
-        self.time >= self.warning_time + self.show_time
+        self.life.done()
     }
 }
@@ -1,9 +1,10 @@
 use std::f32::{consts::{PI, TAU, FRAC_PI_2}, NEG_INFINITY};
 
-use macroquad::{prelude::{vec2, ORANGE, BLACK, WHITE, Vec2, RED, YELLOW, SKYBLUE, GRAY, Color}, window::{screen_width, screen_height}, rand::gen_range};
+use macroquad::{prelude::{vec2, ORANGE, BLACK, WHITE, Vec2, RED, YELLOW, SKYBLUE, GRAY, Color}, rand::gen_range};
 
 // imports galore
 use crate::{
+    CanErr,
     game::{GameState, GSEvent, UpdateAccumulator, ModifyArgs},
     generators::{repeat_periodic, clone_offset, remove},
     spawners::{HorLaserSpawner, LaserSpawner, BombSideSpawner},
@@ -15,8 +16,9 @@ use crate::{
     utils::{
         cmul, gay, mix, screen_center, screen_size, rand_vec,
         floor_vec, screen, tev_rep, ez, repeat_events, rep_off,
-        gen_sign, sq
-    }
+        gen_sign, sq, screen_width, screen_height
+    },
+    midi::{Midi, MidiMap}
 };
 
 /// Function OBstacle Event
@@ -29,7 +31,7 @@ macro_rules! fobe {
 }
 
 /// F-777 - Inferno (Pyro's Theme)
-pub fn inferno(state: &mut GameState) -> (f32, f32, &'static str) {
+pub fn inferno(state: &mut GameState) -> CanErr {
     let rapid_lasers = repeat_periodic(LaserSpawner::new(2.0, 1.0, 45.0, 10.0), 96, 2.0, 0.25);
     let snare_bombs = repeat_periodic(BombSideSpawner::new(12, 200.0, 12.5, 2.0), 7, 4.0, 4.0);
     let drops = 12;
@@ -83,13 +85,12 @@ pub fn inferno(state: &mut GameState) -> (f32, f32, &'static str) {
         .chain(spiralsurge)
         .chain(rise)
     );
-    (0.0, 170.0, "music/inferno.mp3")
+    Ok(())
 }
 
 /// Meganeko - Moonlight Sonata (3rd Movement) Remix
-pub fn moonlight_sonata(state: &mut GameState) -> (f32, f32, &'static str) {
+pub fn moonlight_sonata(state: &mut GameState) -> CanErr {
     state.clear_events();
-    let bpm = 177.5;
 
     // Bombs
     state.add_event(GSEvent::new(-11.0, |accum: &mut UpdateAccumulator, _| {
@@ -175,39 +176,9 @@ pub fn moonlight_sonata(state: &mut GameState) -> (f32, f32, &'static str) {
 
     // Rotatable rectangles
     state.add_event(GSEvent(8.0, Box::new(|accum: &mut UpdateAccumulator, _| {
-        accum.obst(RotatingRect {
-            center: screen_center(),
-            size: vec2(screen_width() * 2.0, 50.0),
-            rot: 0.0,
-            warning_time: 8.0,
-            show_time: 24.0,
-            current_time: 0.0,
-            ease_time: 0.0,
-            grow_time: 1.0,
-            rpb: 0.05
-        });
-        accum.obst(RotatingRect {
-            center: screen_center(),
-            size: vec2(50.0, screen_height() * 2.0),
-            rot: 0.0,
-            warning_time: 8.0,
-            show_time: 24.0,
-            current_time: 0.0,
-            ease_time: 0.0,
-            grow_time: 1.0,
-            rpb: 0.05
-        });
-        accum.obst(RotatingRect {
-            center: screen_center(),
-            size: vec2(250.0, 250.0),
-            rot: 0.0,
-            warning_time: 8.0,
-            show_time: 24.0,
-            current_time: 0.0,
-            ease_time: 0.0,
-            grow_time: 1.0,
-            rpb: 0.05
-        });
+        accum.obst(RotatingRect::default().size(vec2(screen_width() * 2.0, 50.0)).warning_time(8.0).show_time(24.0).grow_time(1.0).rpb(0.05));
+        accum.obst(RotatingRect::default().size(vec2(50.0, screen_height() * 2.0)).warning_time(8.0).show_time(24.0).grow_time(1.0).rpb(0.05));
+        accum.obst(RotatingRect::default().size(vec2(250.0, 250.0)).warning_time(8.0).show_time(24.0).grow_time(1.0).rpb(0.05));
         accum.obst(SlamLaser::new(vec2(100.0, -50.0), vec2(100.0, screen_height() + 50.0), 200.0, 8.0, 24.0, 0.2, Vec2::ZERO, 25.0));
         accum.obst(SlamLaser::new(vec2(screen_width() - 100.0, -50.0), vec2(screen_width() - 100.0, screen_height() + 50.0), 200.0, 8.0, 24.0, 0.2, Vec2::ZERO, 25.0));
     })));
@@ -224,15 +195,7 @@ pub fn moonlight_sonata(state: &mut GameState) -> (f32, f32, &'static str) {
     state.add_event(GSEvent(-23.1, Box::new(|accum: &mut UpdateAccumulator, _| {
         accum.obst(Periodic::new(28, 0.375, Box::new(|ac: &mut UpdateAccumulator, _| {
             for i in 0..8 {
-                ac.obst(RotatableRect {
-                    center: floor_vec(rand_vec(Vec2::ZERO, screen_size()), vec2(20.0, 20.0)),
-                    size: vec2(20.0, 20.0),
-                    rot: 0.0,
-                    warning_time: 4.0,
-                    show_time: 2.0,
-                    current_time: 0.0,
-                    grow_time: 0.25
-                });
+                ac.obst(RotatableRect::new(floor_vec(rand_vec(Vec2::ZERO, screen_size()), vec2(20.0, 20.0)), vec2(20.0, 20.0), 0.0, 4.0, 2.0, 0.25));
             }
         })));
     })));
@@ -253,37 +216,26 @@ pub fn moonlight_sonata(state: &mut GameState) -> (f32, f32, &'static str) {
             gs.fg_raw(Box::new(|f: f32| cmul(WHITE, (f * TAU * 2.0).sin() / 4.0 + 0.75)));
         })
     ]);
-
-    (-9.7327210884 * bpm / 60.0, bpm, "./music/moonlight_sonata.mp3")
+    Ok(())
 }
 
 /// Aperture Science Psychoacoustic Laboratories - Friendly Faith Plate
-pub fn friendly_faith_plate(state: &mut GameState) -> (f32, f32, &'static str) {
-    let bpm: f32 = 120.0;
+pub fn friendly_faith_plate(state: &mut GameState) -> CanErr {
     state.instantly(|accum: &mut UpdateAccumulator, _| {
         accum.fg(cmul(WHITE, 0.75));
     });
     state.add_events(vec![].into_iter()
-        .chain(rep_off([
-            0.0, 0.25, 0.5, 1.0, 1.5, 1.75,
-            2.0, 2.5, 2.75, 3.25, 3.5,
-            4.0, 4.25, 4.5, 4.75, 5.0, 5.25, 5.5, 5.75,
-            6.0, 6.25, 6.5, 7.0, 7.25, 7.5
-        ], 4, 8.0).into_iter().map(|n|
-            GSEvent::new(n - 2.0, move |accum: &mut UpdateAccumulator, _| {
-                let w = screen_width();
-                for _ in 0..1 {
-                    accum.obst(GrowLaser::new(
-                        vec2(gen_range(w, w * 3.0), -20.0 - screen_height()),
-                        vec2(gen_range(-w * 2.0, 0.0), screen_height() * 2.0 + 20.0),
-                        50.0, 2.0, 1.0, Vec2::ZERO)
-                            .grow_time(0.125)
-                            .fade_in(0.125)
-                            .fade_opacity(0.25)
-                    );
-                }
-            })
-        ))
+        .chain(Midi::load("midi/friendly_faith_plate.mid")?.events(&MidiMap::new().channel(0, 2.0, |accum: &mut UpdateAccumulator, _| {
+            let w = screen_width();
+            accum.obst(GrowLaser::new(
+                vec2(gen_range(w, w * 3.0), -20.0 - screen_height()),
+                vec2(gen_range(-w * 2.0, 0.0), screen_height() * 2.0 + 20.0),
+                50.0, 2.0, 1.0, Vec2::ZERO)
+                    .grow_time(0.125)
+                    .fade_in(0.125)
+                    .fade_opacity(0.25)
+            );
+        }), 0.0))
         .chain(repeat_periodic(|accum: &mut UpdateAccumulator, _| {
             for i in 0..2 {
                 let pos = vec2(screen_width(), gen_range(screen_height() * 0.1, screen_height() * 0.9));
@@ -294,12 +246,11 @@ pub fn friendly_faith_plate(state: &mut GameState) -> (f32, f32, &'static str) {
             }
         }, 32, 31.0, 1.0))
     );
-    (-2.05, bpm, "music/[120] friendly_faith_plate.mp3")
+    Ok(())
 }
 
 /// Cowbell Cult - Smoke (feat. JOEHDAH)
-pub fn smoke(state: &mut GameState) -> (f32, f32, &'static str) {
-    let bpm = 74.0;
+pub fn smoke(state: &mut GameState) -> CanErr {
     let mult = 1.1;
     state.add_events([
         GSEvent(-1.0, Box::new(|accum: &mut UpdateAccumulator, _| {
@@ -498,12 +449,11 @@ pub fn smoke(state: &mut GameState) -> (f32, f32, &'static str) {
             accum.fg(cmul(mix(WHITE, RED, 0.5), 0.6));
         }))
     ]);
-    (-1.678 * bpm / 60.0, bpm, "./music/smoke.mp3")
+    Ok(())
 }
 
 /// Shirobon - Granite
-pub fn granite(state: &mut GameState) -> (f32, f32, &'static str) {
-    let bpm = 128.0;
+pub fn granite(state: &mut GameState) -> CanErr {
     state.add_event(GSEvent(62.0, Box::new(|accum: &mut UpdateAccumulator, _| {
         accum.obst(GOLGrid::default()
             .dims(64, 36)
@@ -518,12 +468,11 @@ pub fn granite(state: &mut GameState) -> (f32, f32, &'static str) {
         accum.fg(SKYBLUE);
         accum.float(40.0);
     })));
-    (0.05 / bpm * 60.0, bpm, "music/granite.mp3")
+    Ok(())
 }
 
 /// Nighthawk22 - Isolation (LIMBO Remix)
-pub fn isolation(state: &mut GameState) -> (f32, f32, &'static str) {
-    let bpm = 200.0;
+pub fn isolation(state: &mut GameState) -> CanErr {
     state.instantly(Box::new(|accum: &mut UpdateAccumulator, _| {
         accum.bg(Color::new(0.0, 0.1, 0.1, 1.0));
         let color1 = Color::new(0.0, 1.0, 0.75, 1.0);
@@ -556,12 +505,11 @@ pub fn isolation(state: &mut GameState) -> (f32, f32, &'static str) {
             }
         }
     })));
-    (-8.442 * bpm / 60.0, bpm, "music/isolation.mp3")
+    Ok(())
 }
 
 /// KOCMOC (Albee Remix)
-pub fn kocmoc(state: &mut GameState) -> (f32, f32, &'static str) {
-    let bpm = 95.0;
+pub fn kocmoc(state: &mut GameState) -> CanErr {
 
     state.instantly(|accum: &mut UpdateAccumulator, _| {
         accum.float(20.0);
@@ -726,18 +674,14 @@ pub fn kocmoc(state: &mut GameState) -> (f32, f32, &'static str) {
             (t * 2.0).sin() * 0.5 + 0.5
         )));
     });
-
-    (-21.294 * bpm / 60.0, bpm, "music/kocmoc2.mp3")
+    Ok(())
 }
-pub fn sparkler(state: &mut GameState) -> (f32, f32, &'static str) {
-    let bpm = 108.5;
-    
-    (-17.886 * bpm / 60.0, bpm, "music/sparkler.mp3")
+pub fn sparkler(state: &mut GameState) -> CanErr {
+    Ok(())
 }
 // Tanger - Firestarter
-pub fn firestarter(state: &mut GameState) -> (f32, f32, &'static str) {
+pub fn firestarter(state: &mut GameState) -> CanErr {
     let diag_rad = (sq(screen_height()) + sq(screen_width())).sqrt();
-    let bpm = 135.0;
     state.instantly(|accum: &mut UpdateAccumulator, _| {
         accum.bg(cmul(SKYBLUE, 0.2));
         accum.fg(ORANGE);
@@ -758,5 +702,5 @@ pub fn firestarter(state: &mut GameState) -> (f32, f32, &'static str) {
     state.event(-2.0, |accum: &mut UpdateAccumulator, _| {
         accum.obst(SlamLaser::new(screen(0.5, -0.1), screen(0.5, 1.1), 200.0, 2.0, 2.0, 0.2, vec2(0.0, 0.0), 80.0));
     });
-    (-1.978 * bpm / 60.0, bpm, "music/firestarter.mp3")
+    Ok(())
 }
\ No newline at end of file
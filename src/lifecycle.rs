@@ -0,0 +1,103 @@
+//! The phases a telegraphed obstacle goes through, shared so they all warn, appear and leave the same way.\
+//! Warning: faded in, harmless. Active: dangerous, flashing white as it appears. Leaving: still dangerous, on its way out.
+use macroquad::prelude::{Color, WHITE};
+
+use crate::utils::mix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Warning,
+    Active,
+    Leaving,
+    Done,
+}
+
+/// Timing for an obstacle, in beats since it spawned. Obstacles hold one and call `update` with their time.
+#[derive(Debug, Clone, Copy)]
+pub struct Lifecycle {
+    /// Beats of harmless telegraphing before it turns dangerous.
+    pub warning: f32,
+    /// Beats it's dangerous for, including `leave`.
+    pub show: f32,
+    /// Beats at the start of `show` spent appearing (flashing from white, growing in).
+    pub enter: f32,
+    /// Beats at the end of `show` spent leaving (shrinking, retracting).
+    pub leave: f32,
+    /// Beats at the start of `warning` spent fading in to `warning_opacity`. `None` fades in over the whole warning.
+    pub fade_in: Option<f32>,
+    pub warning_opacity: f32,
+    pub time: f32,
+    activated: bool,
+}
+impl Default for Lifecycle {
+    fn default() -> Self { Self::new(0.0, 0.0) }
+}
+impl Lifecycle {
+    /// Fades in over the whole warning to half opacity, and appears and leaves instantly.
+    pub fn new(warning: f32, show: f32) -> Self {
+        Lifecycle { warning, show, enter: 0.0, leave: 0.0, fade_in: None, warning_opacity: 0.5, time: 0.0, activated: false }
+    }
+    pub fn enter(mut self, enter: f32) -> Self { self.enter = enter; self }
+    pub fn leave(mut self, leave: f32) -> Self { self.leave = leave; self }
+    pub fn fade_in(mut self, fade_in: f32) -> Self { self.fade_in = Some(fade_in); self }
+    pub fn warning_opacity(mut self, warning_opacity: f32) -> Self { self.warning_opacity = warning_opacity; self }
+    pub fn update(&mut self, time: f32) {
+        self.time = time;
+    }
+    pub fn total(&self) -> f32 {
+        self.warning + self.show
+    }
+    pub fn phase(&self) -> Phase {
+        if self.time < self.warning {
+            Phase::Warning
+        } else if self.time >= self.total() {
+            Phase::Done
+        } else if self.time >= self.total() - self.leave {
+            Phase::Leaving
+        } else {
+            Phase::Active
+        }
+    }
+    /// Whether it can hurt. Nothing collides while warning.
+    pub fn dangerous(&self) -> bool {
+        matches!(self.phase(), Phase::Active | Phase::Leaving)
+    }
+    /// For `Obstacle::should_kill`.
+    pub fn done(&self) -> bool {
+        self.phase() == Phase::Done
+    }
+    /// True once, on the first update that's no longer warning. For one-off effects like jerks and sounds.
+    pub fn just_activated(&mut self) -> bool {
+        let now = self.time >= self.warning;
+        let just = now && !self.activated;
+        self.activated |= now;
+        just
+    }
+    /// 0-1 through the warning, 1 after.
+    pub fn warning_progress(&self) -> f32 {
+        if self.warning <= 0.0 { 1.0 } else { (self.time / self.warning).clamp(0.0, 1.0) }
+    }
+    /// 0-1 through appearing, 0 while warning and 1 after.
+    pub fn enter_progress(&self) -> f32 {
+        if self.enter <= 0.0 { (self.time >= self.warning) as u8 as f32 } else { ((self.time - self.warning) / self.enter).clamp(0.0, 1.0) }
+    }
+    /// 0-1 through leaving, 0 before it starts.
+    pub fn leave_progress(&self) -> f32 {
+        if self.leave <= 0.0 { self.done() as u8 as f32 } else { ((self.time - (self.total() - self.leave)) / self.leave).clamp(0.0, 1.0) }
+    }
+    /// How opaque the telegraph is; 1 once it's dangerous.
+    pub fn opacity(&self) -> f32 {
+        if self.time >= self.warning { return 1.0; }
+        let fade_in = self.fade_in.unwrap_or(self.warning);
+        let fade = if fade_in <= 0.0 { 1.0 } else { (self.time / fade_in).min(1.0) };
+        fade * self.warning_opacity
+    }
+    /// The standard look: faded while warning, flashing from white while appearing.
+    pub fn color(&self, normal: Color) -> Color {
+        match self.phase() {
+            Phase::Warning => Color { a: normal.a * self.opacity(), ..normal },
+            _ if self.enter > 0.0 && self.enter_progress() < 1.0 => mix(WHITE, normal, self.enter_progress()),
+            _ => normal,
+        }
+    }
+}
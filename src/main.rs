@@ -11,10 +11,12 @@ use macroquad::{window::next_frame, prelude::*, time::get_frame_time};
 use soloud::{Soloud, SoloudFlag, Backend, Wav, AudioExt, LoadExt};
 use strum::{IntoEnumIterator, EnumCount};
 
-use sound::Music;
+use sound::{Music, SfxCreator, Mixer, Preview};
 use game::{GameState, LevelState};
 use state_control::{EparState, EparLevel};
-use utils::{screen_size, cmul};
+use utils::{screen_size, screen_width, screen_height, cmul, centered_text_draw, clear_virtual, VIRTUAL_WIDTH, VIRTUAL_HEIGHT};
+use analysis::Analysis;
+use chart::Chart;
 
 mod sound;
 mod utils;
@@ -24,6 +26,16 @@ mod spawners;
 mod generators;
 mod game;
 mod state_control;
+mod analysis;
+mod chart;
+mod midi;
+mod endless;
+mod broadphase;
+mod sat;
+mod lifecycle;
+mod camera;
+mod arena;
+mod tween;
 
 type AnyErr = Box<dyn Error>;
 type Possibly<T> = Result<T, AnyErr>;
@@ -35,58 +47,151 @@ type CanErr = Possibly<()>;
 //#[inline]
 //fn vec2((x, y): (f32, f32)) -> Vec2 { Vec2::new(x, y) }
 
+/// Where endless mode looks for songs.
+const SONGS_DIR: &str = "songs";
+
+/// Draws entry `idx` of `length` entries filling the screen, and returns whether it's hovered.
+fn menu_item(idx: usize, length: usize, txt: &str, txt_color: Color, mouse_pos: Vec2) -> bool {
+    let rect_height = screen_height() / length as f32;
+    let rect_width = screen_width();
+    let rect_padding = 10.0;
+    let y_offset = -((length as f32 - 1.0) / 2.0 - idx as f32) * rect_height + screen_height() / 2.0;
+    let x_offset = screen_width() / 2.0;
+    let rsize = vec2(rect_width, rect_height) - rect_padding;
+    let r = Rect::new(x_offset - rsize.x / 2.0, y_offset - rsize.y / 2.0, rsize.x, rsize.y);
+    let hovered = r.contains(mouse_pos);
+    draw_rectangle(r.x, r.y, r.w, r.h, cmul(WHITE, if hovered { 0.3 } else { 0.1 }));
+    let fsize = 40;
+    let dims = measure_text(txt, None, fsize, 1.0);
+    draw_text(txt, x_offset - dims.width / 2.0, y_offset + dims.offset_y / 2.0, fsize as f32, txt_color);
+    hovered
+}
+
 #[macroquad::main("Exclusively Polygons Alonside Rhythms")]
 async fn main() -> CanErr {
     let start = 0.0;
     let speed = 1.0;
 
-    request_new_screen_size(1600.0, 900.0);
+    // Tool mode: `draft <song> [out]` writes a chart drafted from the song's onsets
+    let args = std::env::args().collect::<Vec<String>>();
+    if let (Some("draft"), Some(song)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let out = args.get(3).cloned().unwrap_or_else(|| format!("{song}.chart"));
+        let analysis = Analysis::of_file(song)?;
+        let chart = Chart::draft(song, &analysis, 4.0);
+        chart.save(&out)?;
+        println!("{:.2} bpm, {} markers -> {out}", chart.bpm, chart.markers.len());
+        return Ok(());
+    }
+
+    request_new_screen_size(VIRTUAL_WIDTH, VIRTUAL_HEIGHT);
     next_frame().await;
     let sl = Arc::new(Mutex::new(Soloud::new(SoloudFlag::empty(), Backend::Auto, 44100, 1024, 2)?));
-    //let sfx = SfxCreator::new(sl.clone());
-    let mut state = GameState::new(Music::new(sl.clone()));
+    let mixer = Mixer::new(sl.clone());
+    let sfx = SfxCreator::new(sl.clone(), mixer.sfx_bus());
+    let preview = Preview::new(sl.clone(), mixer.music_bus());
+    let mut state = GameState::new(Music::new(sl.clone(), mixer.music_bus()), sfx, mixer, preview);
+    let mut selected_bus = 0;
+    let mut previewing = None;
+    // `chart <file>` plays a chart straight away
+    if let (Some("chart"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        let chart = Chart::load(path)?;
+        state.state = EparState::InGame(Box::new(LevelState::new()));
+        state.load_chart(&chart, start, speed)?;
+    }
+    // `endless <song>` plays endless mode on any song
+    if let (Some("endless"), Some(path)) = (args.get(1).map(String::as_str), args.get(2)) {
+        state.state = EparState::InGame(Box::new(LevelState::new()));
+        state.load_endless(path)?;
+    }
     loop {
+        state.mixer.update(get_frame_time());
         match &mut state.state {
             EparState::MainMenu => {
+                clear_virtual(BLACK);
+                if is_key_pressed(KeyCode::Left) { selected_bus = (selected_bus + Mixer::BUSES.len() - 1) % Mixer::BUSES.len(); }
+                if is_key_pressed(KeyCode::Right) { selected_bus = (selected_bus + 1) % Mixer::BUSES.len(); }
+                let volume = state.mixer.bus_volume(selected_bus);
+                if is_key_pressed(KeyCode::Up) { *volume = (*volume + 0.1).min(1.0); }
+                if is_key_pressed(KeyCode::Down) { *volume = (*volume - 0.1).max(0.0); }
+
                 let show_unfinished = is_key_down(KeyCode::U);
                 let lvls = EparLevel::iter().filter(move |lvl| show_unfinished || lvl.finished()).collect::<Vec<_>>();
-                let length = lvls.len();
-                let rect_height = screen_height() / length as f32;
-                let rect_width = screen_width();
-                let rect_padding = 10.0;
-                let pos = mouse_position();
-                let mouse_pos = vec2(pos.0, pos.1);
-                'elit: for (idx, lvl) in lvls.into_iter().enumerate() {
-                    let y_offset = -((length as f32 - 1.0) / 2.0 - idx as f32) * rect_height + screen_height() / 2.0;
-                    let x_offset = screen_width() / 2.0;
-                    let rsize = vec2(rect_width, rect_height) - rect_padding;
-                    let rpos = vec2(x_offset, y_offset);
-                    let r = Rect::new(x_offset - rsize.x / 2.0, y_offset - rsize.y / 2.0, rsize.x, rsize.y);
-    
-                    let color;
-                    if r.contains(mouse_pos){
-                        color = cmul(WHITE, 0.3);
-                        if is_mouse_button_pressed(MouseButton::Left) {
+                let mouse_pos = utils::mouse_pos();
+                let mut hovered = None;
+                if is_key_down(KeyCode::E) {
+                    // endless mode on anything in the songs folder
+                    let songs = std::fs::read_dir(SONGS_DIR).map(|dir| dir
+                        .filter_map(|entry| Some(entry.ok()?.path().to_str()?.to_string()))
+                        .collect::<Vec<_>>()
+                    ).unwrap_or_default();
+                    if songs.is_empty() {
+                        centered_text_draw(&format!("Put songs in {SONGS_DIR}/ to play them endlessly"), screen_size() / 2.0, 40.0, WHITE);
+                    }
+                    for (idx, song) in songs.iter().enumerate() {
+                        if menu_item(idx, songs.len(), song, WHITE, mouse_pos) && is_mouse_button_pressed(MouseButton::Left) {
+                            clear_virtual(BLACK);
+                            centered_text_draw("Analyzing...", screen_size() / 2.0, 40.0, WHITE);
+                            next_frame().await;
                             macroquad::rand::srand((get_time() * 1_000_000.0) as u64);
-                            state.state = EparState::InGame(LevelState::new());
+                            previewing = None;
+                            state.state = EparState::InGame(Box::new(LevelState::new()));
                             state.reset();
-                            state.load_level(lvl, start, speed);
-                            break 'elit;
+                            if let Err(e) = state.load_endless(song) {
+                                println!("Could not play {song}: {e}");
+                                state.exit();
+                            }
+                            break;
+                        }
+                    }
+                } else {
+                    let length = lvls.len();
+                    'elit: for (idx, lvl) in lvls.into_iter().enumerate() {
+                        if menu_item(idx, length, &format!("{lvl}"), if lvl.finished() { WHITE } else { RED }, mouse_pos) {
+                            hovered = Some(lvl);
+                            if is_mouse_button_pressed(MouseButton::Left) {
+                                macroquad::rand::srand((get_time() * 1_000_000.0) as u64);
+                                previewing = None;
+                                if is_key_down(KeyCode::R) {
+                                    if let Err(e) = state.record_level(lvl) { println!("Could not record: {e}"); }
+                                    break 'elit;
+                                }
+                                state.state = EparState::InGame(Box::new(LevelState::new()));
+                                state.reset();
+                                if let Err(e) = state.load_level(lvl, start, speed) {
+                                    println!("Could not load {lvl}: {e}");
+                                    state.exit();
+                                }
+                                break 'elit;
+                            }
+                        }
+                    }
+                }
+                if matches!(state.state, EparState::MainMenu) && hovered != previewing {
+                    previewing = hovered;
+                    match hovered {
+                        Some(lvl) => {
+                            let (offset, bpm, audiofile) = lvl.info();
+                            let start = ((lvl.preview_beat() - offset) * 60.0 / bpm).max(0.0);
+                            if let Err(e) = state.preview.play(audiofile, start, state.mixer.fade_time) {
+                                println!("Could not preview {audiofile}: {e}");
+                            }
                         }
-                    } else {
-                        color = cmul(WHITE, 0.1);
+                        None => state.preview.stop(state.mixer.fade_time)
                     }
-                    draw_rectangle(x_offset - rsize.x / 2.0, y_offset - rsize.y / 2.0, rsize.x, rsize.y, color);
-                    let fsize = 40;
-                    let txt = &format!("{lvl}");
-                    let dims = measure_text(txt, None, fsize, 1.0);
-                    draw_text(txt, x_offset - dims.width / 2.0, y_offset + dims.offset_y / 2.0, fsize as f32, if lvl.finished() { WHITE } else { RED });
+                }
+                state.preview.update();
+                for (idx, name) in Mixer::BUSES.into_iter().enumerate() {
+                    let txt = format!("{name}: {:.0}%", *state.mixer.bus_volume(idx) * 100.0);
+                    let color = if idx == selected_bus { WHITE } else { GRAY };
+                    draw_text(&txt, 10.0 + idx as f32 * 160.0, screen_height() - 10.0, 24.0, color);
                 }
                 next_frame().await;
             }
             EparState::InGame(ls) => {
                 ls.player.pos = vec2(0.125, 0.5) * screen_size();
+                ls.player.prev_pos = ls.player.pos;
                 while state.mus.is_playing() {
+                    state.mixer.update(get_frame_time());
                     state.mus.check();
                     if let Some(f) = state.mus.current_beat() {
                         let ft = get_frame_time();
@@ -96,7 +201,41 @@ async fn main() -> CanErr {
                     state.draw();
                     next_frame().await;
                 }
-                state.state = EparState::MainMenu;
+                // however the level ended, don't leave its filters (or a hit's muffle) on the menu previews
+                state.mixer.reset_filters();
+                state.state = match &state.state {
+                    EparState::InGame(ls) if !ls.quit => EparState::Results(ls.results()),
+                    _ => EparState::MainMenu,
+                };
+            }
+            EparState::Results(results) => {
+                results.draw();
+                if is_mouse_button_pressed(MouseButton::Left) || is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter) {
+                    state.state = EparState::MainMenu;
+                }
+                next_frame().await;
+            }
+            EparState::Recording(_) => {
+                while state.mus.is_playing() && !is_key_pressed(KeyCode::Escape) {
+                    state.mixer.update(get_frame_time());
+                    state.mus.check();
+                    if let Some(beat) = state.mus.current_beat() {
+                        if let EparState::Recording(rec) = &mut state.state {
+                            rec.update(beat, get_frame_time());
+                            rec.draw(beat);
+                        }
+                        state.tick_metronome(beat);
+                        state.draw_metronome(beat);
+                    }
+                    next_frame().await;
+                }
+                if let EparState::Recording(rec) = &mut state.state {
+                    match rec.save() {
+                        Ok(()) => println!("Saved {} markers to {}", rec.chart.markers.len(), rec.path),
+                        Err(e) => println!("Could not save chart: {e}"),
+                    }
+                }
+                state.exit();
             }
         }
     }
@@ -0,0 +1,232 @@
+//! A minimal Standard MIDI File reader, so rhythms can be authored in a DAW instead of typed out as beat arrays.\
+//! Only note on/off is kept; tempo changes are ignored, since timing comes from the song's bpm like everywhere else.
+use std::fs;
+
+use crate::{Possibly, game::{GSEvent, Accumulatee, UpdateAccumulator, ModifyArgs}};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    /// Beats from the start of the file (ticks / ticks per quarter note).
+    pub beat: f32,
+    pub length: f32,
+    /// 0-15.
+    pub channel: u8,
+    pub pitch: u8,
+    /// 0-1.
+    pub velocity: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Midi {
+    /// Sorted by beat.
+    pub notes: Vec<Note>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Possibly<u8> {
+        let b = *self.data.get(self.pos).ok_or("unexpected end of midi data")?;
+        self.pos += 1;
+        Ok(b)
+    }
+    fn bytes(&mut self, len: usize) -> Possibly<&'a [u8]> {
+        let slice = self.data.get(self.pos..self.pos + len).ok_or("unexpected end of midi data")?;
+        self.pos += len;
+        Ok(slice)
+    }
+    fn u16(&mut self) -> Possibly<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into()?))
+    }
+    fn u32(&mut self) -> Possibly<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into()?))
+    }
+    /// Variable-length quantity: 7 bits per byte, high bit set on all but the last.
+    fn var(&mut self) -> Possibly<u32> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let b = self.byte()?;
+            value = (value << 7) | (b & 0x7f) as u32;
+            if b & 0x80 == 0 { return Ok(value); }
+        }
+        Err("midi variable-length value too long".into())
+    }
+}
+
+impl Midi {
+    pub fn load(path: &str) -> Possibly<Self> {
+        let data = fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+        Self::parse(&data).map_err(|e| format!("{path}: {e}").into())
+    }
+    pub fn parse(data: &[u8]) -> Possibly<Self> {
+        let mut r = Reader { data, pos: 0 };
+        if r.bytes(4)? != b"MThd" { return Err("not a midi file".into()); }
+        let header_len = r.u32()? as usize;
+        let _format = r.u16()?;
+        let tracks = r.u16()?;
+        let division = r.u16()?;
+        if division & 0x8000 != 0 { return Err("SMPTE time division is not supported".into()); }
+        let ppq = division as f32;
+        r.pos = 8 + header_len;
+
+        let mut notes = vec![];
+        for _ in 0..tracks {
+            let id = r.bytes(4)?;
+            let len = r.u32()? as usize;
+            let track = r.bytes(len)?;
+            if id != b"MTrk" { continue; }
+            Self::parse_track(track, ppq, &mut notes)?;
+        }
+        notes.sort_by(|a: &Note, b| a.beat.total_cmp(&b.beat));
+        Ok(Midi { notes })
+    }
+    fn parse_track(track: &[u8], ppq: f32, notes: &mut Vec<Note>) -> Possibly<()> {
+        let mut r = Reader { data: track, pos: 0 };
+        let mut tick = 0u32;
+        let mut status = 0u8;
+        // index into `notes` of the note still held on each channel & pitch
+        let mut held = vec![None; 16 * 128];
+        while r.pos < track.len() {
+            tick += r.var()?;
+            let beat = tick as f32 / ppq;
+            let mut b = r.byte()?;
+            if b & 0x80 != 0 {
+                status = b;
+                if status < 0xf0 { b = r.byte()?; }
+            } // otherwise running status; `b` is already the first data byte
+            match status & 0xf0 {
+                0x80 | 0x90 => {
+                    let (channel, pitch, vel) = (status & 0x0f, b & 0x7f, r.byte()?);
+                    let key = channel as usize * 128 + pitch as usize;
+                    // a note on with velocity 0 is a note off
+                    if let Some(idx) = held[key].take() {
+                        let note: &mut Note = &mut notes[idx];
+                        note.length = beat - note.beat;
+                    }
+                    if status & 0xf0 == 0x90 && vel > 0 {
+                        held[key] = Some(notes.len());
+                        notes.push(Note { beat, length: 0.0, channel, pitch, velocity: vel as f32 / 127.0 });
+                    }
+                }
+                0xa0 | 0xb0 | 0xe0 => { r.byte()?; }
+                0xc0 | 0xd0 => {}
+                _ => match status {
+                    0xff => {
+                        let _kind = r.byte()?;
+                        let len = r.var()? as usize;
+                        r.bytes(len)?;
+                    }
+                    0xf0 | 0xf7 => {
+                        let len = r.var()? as usize;
+                        r.bytes(len)?;
+                    }
+                    _ => return Err(format!("unknown midi status {status:#x}").into()),
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Turns every note matched by `map` into an event, `align` beats later.\
+    /// `align` is whatever lines the start of the MIDI file up with beat 0 of the level.
+    pub fn events(&self, map: &MidiMap, align: f32) -> Vec<GSEvent> {
+        let mut events = vec![];
+        for note in &self.notes {
+            for m in map.0.iter().filter(|m| m.matches(note)) {
+                events.push(GSEvent(note.beat + align - m.lead, Box::new(NoteEvent {
+                    spawner: m.spawner.box_clone(),
+                    pitch: note.pitch,
+                    velocity: note.velocity,
+                })));
+            }
+        }
+        events
+    }
+}
+
+pub struct NoteMapping {
+    pub channel: Option<u8>,
+    pub pitches: (u8, u8),
+    /// Beats early to spawn, so that the attack lands on the note.
+    pub lead: f32,
+    pub spawner: Box<dyn Accumulatee>,
+}
+impl NoteMapping {
+    pub fn matches(&self, note: &Note) -> bool {
+        self.channel.is_none_or(|c| c == note.channel) && (self.pitches.0..=self.pitches.1).contains(&note.pitch)
+    }
+}
+impl Clone for NoteMapping {
+    fn clone(&self) -> Self {
+        NoteMapping { spawner: self.spawner.box_clone(), ..*self }
+    }
+}
+
+/// Which notes spawn what. A note can match several mappings, and spawns all of them.
+#[derive(Clone, Default)]
+pub struct MidiMap(pub Vec<NoteMapping>);
+impl MidiMap {
+    pub fn new() -> Self { Self::default() }
+    /// Any note on `channel` (or any channel with `None`) between `low` and `high` inclusive.
+    pub fn range(mut self, channel: Option<u8>, low: u8, high: u8, lead: f32, spawner: impl Accumulatee + 'static) -> Self {
+        self.0.push(NoteMapping { channel, pitches: (low, high), lead, spawner: Box::new(spawner) });
+        self
+    }
+    pub fn pitch(self, channel: Option<u8>, pitch: u8, lead: f32, spawner: impl Accumulatee + 'static) -> Self {
+        self.range(channel, pitch, pitch, lead, spawner)
+    }
+    pub fn channel(self, channel: u8, lead: f32, spawner: impl Accumulatee + 'static) -> Self {
+        self.range(Some(channel), 0, 127, lead, spawner)
+    }
+}
+
+/// Runs a spawner with the note's pitch and velocity in its `ModifyArgs`.
+pub struct NoteEvent {
+    pub spawner: Box<dyn Accumulatee>,
+    pub pitch: u8,
+    pub velocity: f32,
+}
+impl Clone for NoteEvent {
+    fn clone(&self) -> Self {
+        NoteEvent { spawner: self.spawner.box_clone(), ..*self }
+    }
+}
+impl Accumulatee for NoteEvent {
+    fn box_clone(&self) -> Box<dyn Accumulatee> { Box::new(self.clone()) }
+    fn run(&self, to_add: &mut UpdateAccumulator, args: ModifyArgs) {
+        self.spawner.run(to_add, args.pitch(self.pitch).velocity(self.velocity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One track at 96 ticks per beat: a note ended by a running status note on with velocity 0, and two overlapping
+    /// notes on different channels, one of them after a two-byte delta.
+    const SMF: &[u8] = &[
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+        b'M', b'T', b'r', b'k', 0, 0, 0, 27,
+        0, 0x90, 60, 100,
+        48, 60, 0,
+        48, 64, 80,
+        0, 0x91, 67, 127,
+        0x81, 0x40, 0x80, 64, 0,
+        0, 0x81, 67, 64,
+        0, 0xff, 0x2f, 0,
+    ];
+
+    #[test]
+    fn parses_running_status_and_zero_velocity_note_off() {
+        let midi = Midi::parse(SMF).unwrap();
+        let notes = midi.notes.iter().map(|n| (n.beat, n.length, n.channel, n.pitch)).collect::<Vec<_>>();
+        assert_eq!(notes, [(0.0, 0.5, 0, 60), (1.0, 2.0, 0, 64), (1.0, 2.0, 1, 67)]);
+        assert_eq!(midi.notes[0].velocity, 100.0 / 127.0);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        assert!(Midi::parse(&SMF[..SMF.len() - 5]).is_err());
+    }
+}
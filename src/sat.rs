@@ -0,0 +1,152 @@
+//! Separating axis tests for convex polygons and circles.\
+//! Two convex shapes don't overlap exactly when some axis (an edge normal of either) has their projections apart.
+use macroquad::prelude::{Vec2, vec2};
+
+/// A shape to collide, for collision between things other than a circle and whatever an obstacle is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hitbox {
+    Circle(Vec2, f32),
+    /// Convex, in either winding order.
+    Polygon(Vec<Vec2>),
+}
+impl Hitbox {
+    /// A rectangle `size` big around `center`, rotated by `rot` radians like `utils::collide_cr`.
+    pub fn rect(center: Vec2, size: Vec2, rot: f32) -> Self {
+        Hitbox::Polygon(rect_corners(center, size, rot).to_vec())
+    }
+    pub fn collides(&self, other: &Hitbox) -> bool {
+        match (self, other) {
+            (Hitbox::Circle(p1, r1), Hitbox::Circle(p2, r2)) => (*p1 - *p2).length_squared() <= (r1 + r2) * (r1 + r2),
+            (Hitbox::Polygon(poly), Hitbox::Circle(pos, rad)) | (Hitbox::Circle(pos, rad), Hitbox::Polygon(poly)) => collide_pc(poly, *pos, *rad),
+            (Hitbox::Polygon(a), Hitbox::Polygon(b)) => collide_pp(a, b),
+        }
+    }
+}
+
+/// Corners of a rotated rectangle, in order around it.
+pub fn rect_corners(center: Vec2, size: Vec2, rot: f32) -> [Vec2; 4] {
+    let (sin, cos) = rot.sin_cos();
+    let corner = |x: f32, y: f32| {
+        let local = size * vec2(x, y);
+        center + vec2(local.x * cos - local.y * sin, local.x * sin + local.y * cos)
+    };
+    [corner(-0.5, -0.5), corner(0.5, -0.5), corner(0.5, 0.5), corner(-0.5, 0.5)]
+}
+
+/// Normals of every edge. Not normalized, which is fine as long as both shapes are projected onto the same one.
+fn edge_normals(poly: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    (0..poly.len()).map(|i| (poly[(i + 1) % poly.len()] - poly[i]).perp())
+}
+
+fn project(poly: &[Vec2], axis: Vec2) -> (f32, f32) {
+    poly.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+        let d = p.dot(axis);
+        (min.min(d), max.max(d))
+    })
+}
+
+/// Tests if two convex polygons overlap.
+pub fn collide_pp(a: &[Vec2], b: &[Vec2]) -> bool {
+    if a.is_empty() || b.is_empty() { return false; }
+    edge_normals(a).chain(edge_normals(b)).all(|axis| {
+        let (amin, amax) = project(a, axis);
+        let (bmin, bmax) = project(b, axis);
+        amin <= bmax && bmin <= amax
+    })
+}
+
+/// Tests if a convex polygon and a circle overlap.\
+/// On top of the edge normals, the axis towards the nearest corner catches circles just off a corner.
+pub fn collide_pc(poly: &[Vec2], pos: Vec2, rad: f32) -> bool {
+    let Some(nearest) = poly.iter().copied().min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos))) else { return false; };
+    let corner_axis = pos - nearest;
+    if corner_axis == Vec2::ZERO { return true; }
+    edge_normals(poly).chain([corner_axis]).all(|axis| {
+        let (min, max) = project(poly, axis);
+        let center = pos.dot(axis);
+        // the axis isn't normalized, so the radius gets scaled the same way
+        let reach = rad * axis.length();
+        center + reach >= min && center - reach <= max
+    })
+}
+
+// Everything below works on any simple polygon, convex or not.
+
+/// Twice the signed area; positive when the points go clockwise on screen (y down).
+fn signed_area(poly: &[Vec2]) -> f32 {
+    (0..poly.len()).map(|i| poly[i].perp_dot(poly[(i + 1) % poly.len()])).sum()
+}
+
+pub fn is_convex(poly: &[Vec2]) -> bool {
+    let turns = (0..poly.len()).map(|i| {
+        let (a, b, c) = (poly[i], poly[(i + 1) % poly.len()], poly[(i + 2) % poly.len()]);
+        (b - a).perp_dot(c - b)
+    });
+    let (mut left, mut right) = (false, false);
+    for turn in turns {
+        left |= turn < 0.0;
+        right |= turn > 0.0;
+    }
+    !(left && right)
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let (d1, d2, d3) = ((b - a).perp_dot(p - a), (c - b).perp_dot(p - b), (a - c).perp_dot(p - c));
+    !((d1 < 0.0 || d2 < 0.0 || d3 < 0.0) && (d1 > 0.0 || d2 > 0.0 || d3 > 0.0))
+}
+
+/// Splits a polygon into triangles by ear clipping, as indices into `poly`.\
+/// Self-intersecting polygons still get triangles, just not sensible ones.
+pub fn triangulate(poly: &[Vec2]) -> Vec<[usize; 3]> {
+    let mut left: Vec<usize> = (0..poly.len()).collect();
+    if signed_area(poly) < 0.0 { left.reverse(); }
+    let mut triangles = vec![];
+    while left.len() > 3 {
+        let n = left.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (poly[left[(i + n - 1) % n]], poly[left[i]], poly[left[(i + 1) % n]]);
+            (b - a).perp_dot(c - b) > 0.0 && !left.iter().any(|&j| {
+                let p = poly[j];
+                p != a && p != b && p != c && in_triangle(p, a, b, c)
+            })
+        });
+        // no ear means the polygon was degenerate or tangled; clip anything so this still finishes
+        let i = ear.unwrap_or(0);
+        triangles.push([left[(i + n - 1) % n], left[i], left[(i + 1) % n]]);
+        left.remove(i);
+    }
+    if left.len() == 3 { triangles.push([left[0], left[1], left[2]]); }
+    triangles
+}
+
+/// Even-odd test, so holes made by overlapping edges count as outside.
+pub fn contains(poly: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for i in 0..poly.len() {
+        let (a, b) = (poly[i], poly[(i + 1) % poly.len()]);
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Tests if a circle touches any simple polygon: either its center is inside, or some edge is within reach.
+pub fn collide_polygon_circle(poly: &[Vec2], pos: Vec2, rad: f32) -> bool {
+    contains(poly, pos) || (0..poly.len()).any(|i| {
+        let (a, b) = (poly[i], poly[(i + 1) % poly.len()]);
+        let t = ((pos - a).dot(b - a) / (b - a).length_squared()).clamp(0.0, 1.0);
+        // zero-length edges give NaN, which clamps to... NaN, so fall back to the endpoint
+        let closest = if t.is_nan() { a } else { a.lerp(b, t) };
+        closest.distance_squared(pos) <= rad * rad
+    })
+}
+
+/// Scales, rotates (like `rect_corners`) and moves points defined around (0, 0).
+pub fn transform(points: &[Vec2], center: Vec2, scale: Vec2, rot: f32) -> Vec<Vec2> {
+    let (sin, cos) = rot.sin_cos();
+    points.iter().map(|p| {
+        let local = *p * scale;
+        center + vec2(local.x * cos - local.y * sin, local.x * sin + local.y * cos)
+    }).collect()
+}
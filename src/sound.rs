@@ -1,54 +1,478 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
-use std::sync::{Arc, Mutex};
+use std::{sync::{Arc, Mutex}, collections::HashMap};
 
 type ThreadSafe<T> = Arc<Mutex<T>>;
 
-use soloud::{Soloud, AudioExt, Handle, SoloudError};
+use macroquad::prelude::Vec2;
+use soloud::{
+    Soloud, AudioExt, Handle, SoloudError, Wav, WavStream, LoadExt, Bus, FilterExt,
+    BiquadResonantFilter, BiquadResonantFilterType, BiquadResonantFilterAttr, EchoFilter, EchoFilterAttr
+};
+
+use crate::utils::screen_width;
+
+/// Where sound effects are loaded from, as `SFX_DIR/<name>.wav`.
+pub const SFX_DIR: &str = "sfx";
+
+/// Sound effects are grouped so that each group can be turned up or down on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SfxCategory {
+    Obstacle,
+    Player,
+    Ui
+}
+
+/// A one-shot sound effect request. Levels and obstacles queue these through `UpdateAccumulator::sfx`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sfx {
+    pub name: &'static str,
+    pub category: SfxCategory,
+    pub volume: f32,
+    /// Where the sound comes from, queued in world space and put on screen before it's played. `None` plays it centered at full volume.
+    pub pos: Option<Vec2>,
+}
+impl Sfx {
+    pub fn new(name: &'static str, category: SfxCategory) -> Self {
+        Sfx { name, category, volume: 1.0, pos: None }
+    }
+    pub fn obstacle(name: &'static str) -> Self { Self::new(name, SfxCategory::Obstacle) }
+    pub fn player(name: &'static str) -> Self { Self::new(name, SfxCategory::Player) }
+    pub fn ui(name: &'static str) -> Self { Self::new(name, SfxCategory::Ui) }
+    pub fn volume(mut self, volume: f32) -> Self { self.volume = volume; self }
+    pub fn at(mut self, pos: Vec2) -> Self { self.pos = Some(pos); self }
+}
+
+const LOWPASS_FILTER: u32 = 0;
+const ECHO_FILTER: u32 = 1;
+/// Soloud's biquad filter tops out at 8kHz, so a fully open low-pass is this.
+pub const LOWPASS_OPEN: f32 = 8000.0;
+
+/// Parameters of the filters sitting on the music bus, which levels can set and animate.\
+/// Both filters start fully dry (wet = 0), so they do nothing until a level turns them up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterParam {
+    LowPassWet,
+    /// Cutoff in Hz (10 - 8000)
+    LowPassFreq,
+    LowPassResonance,
+    EchoWet,
+    EchoDecay
+}
+
+/// Live analysis of whatever is playing on the music bus, refreshed every frame.\
+/// Band energies are normalized against their own recent peaks, so they sit roughly in 0-1 regardless of how loud a song is mastered.
+#[derive(Debug, Clone, Default)]
+pub struct Spectrum {
+    /// 256 bins, straight from Soloud.
+    pub fft: Vec<f32>,
+    pub volume: f32,
+    pub bass: f32,
+    pub mid: f32,
+    pub treble: f32,
+    /// Set on the frame where the low end jumps well above its recent average (e.g. a kick).
+    pub onset: bool,
+    peaks: [f32; 3],
+    flux_avg: f32,
+    since_onset: f32,
+}
+impl Spectrum {
+    /// Upper bounds (exclusive) of the bass and mid bands, in FFT bins of ~86Hz.
+    pub const BANDS: [usize; 2] = [3, 46];
+    /// Peak normalization forgets at this rate per second.
+    const PEAK_DECAY: f32 = 0.1;
+    /// How far above the average flux a frame has to be to count as an onset.
+    const ONSET_RATIO: f32 = 1.6;
+    /// Minimum seconds between onsets.
+    const ONSET_COOLDOWN: f32 = 0.1;
+    pub fn update(&mut self, fft: Vec<f32>, volume: f32, frame_time: f32) {
+        let band = |from: usize, to: usize| fft[from..to].iter().sum::<f32>() / (to - from) as f32;
+        let raw = [band(0, Self::BANDS[0]), band(Self::BANDS[0], Self::BANDS[1]), band(Self::BANDS[1], fft.len())];
+        let mut normalized = [0.0; 3];
+        for i in 0..3 {
+            self.peaks[i] = (self.peaks[i] * (1.0 - Self::PEAK_DECAY * frame_time)).max(raw[i]);
+            normalized[i] = if self.peaks[i] > 0.0 { raw[i] / self.peaks[i] } else { 0.0 };
+        }
+        let flux = if self.fft.len() == fft.len() {
+            fft[..Self::BANDS[1]].iter().zip(&self.fft).map(|(cur, prev)| (cur - prev).max(0.0)).sum::<f32>()
+        } else { 0.0 };
+        self.since_onset += frame_time;
+        self.onset = flux > self.flux_avg * Self::ONSET_RATIO && self.since_onset >= Self::ONSET_COOLDOWN && flux > 0.0;
+        if self.onset { self.since_onset = 0.0; }
+        self.flux_avg += (flux - self.flux_avg) * (frame_time * 4.0).min(1.0);
+        [self.bass, self.mid, self.treble] = normalized;
+        self.volume = volume;
+        self.fft = fft;
+    }
+}
+
+/// Owns the music and SFX buses on the shared `Soloud`, and everything that scales them as a whole.\
+/// `Music` and `SfxCreator` play into the buses by handle, so they never have to know about volumes.
+pub struct Mixer {
+    sl: ThreadSafe<Soloud>,
+    // Buses stop playing when dropped, so they are kept here alongside their handles.
+    music_bus: Bus,
+    sfx_bus: Bus,
+    music_handle: Handle,
+    sfx_handle: Handle,
+    lowpass: BiquadResonantFilter,
+    echo: EchoFilter,
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    /// Seconds taken to fade music in on level start and out on exit.
+    pub fade_time: f32,
+    /// Whether music dips when the player is hit.
+    pub ducking: bool,
+    /// How much of the music volume is taken away by a duck (0-1).
+    pub duck_depth: f32,
+    /// How fast a duck recovers, in volume per second.
+    pub duck_recovery: f32,
+    /// Whether music gets muffled (and slowly unmuffled) when the player is hit.
+    pub muffling: bool,
+    /// Low-pass cutoff used while muffled.
+    pub muffle_freq: f32,
+    duck: f32,
+}
+impl Mixer {
+    pub const BUSES: [&'static str; 3] = ["Master", "Music", "SFX"];
+    pub fn new(sl: ThreadSafe<Soloud>) -> Self {
+        let mut music_bus = Bus::default();
+        let sfx_bus = Bus::default();
+        let mut lowpass = BiquadResonantFilter::default();
+        let mut echo = EchoFilter::default();
+        // Only fails on out-of-range parameters, and these are constant.
+        lowpass.set_params(BiquadResonantFilterType::LowPass, LOWPASS_OPEN, 2.0).unwrap();
+        echo.set_params_ex(0.3, 0.5, 0.0).unwrap();
+        music_bus.set_filter(LOWPASS_FILTER, Some(&lowpass));
+        music_bus.set_filter(ECHO_FILTER, Some(&echo));
+        music_bus.set_visualize_enable(true);
+        let (music_handle, sfx_handle) = {
+            let mut guard = sl.lock().unwrap();
+            let handles = (guard.play(&music_bus), guard.play(&sfx_bus));
+            guard.set_protect_voice(handles.0, true);
+            guard.set_protect_voice(handles.1, true);
+            handles
+        };
+        let mut mixer = Mixer {
+            sl, music_bus, sfx_bus, music_handle, sfx_handle, lowpass, echo,
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+            fade_time: 0.5,
+            ducking: true,
+            duck_depth: 0.6,
+            duck_recovery: 0.5,
+            muffling: true,
+            muffle_freq: 600.0,
+            duck: 0.0
+        };
+        mixer.reset_filters();
+        mixer
+    }
+    pub fn music_bus(&self) -> Handle { self.music_handle }
+    pub fn sfx_bus(&self) -> Handle { self.sfx_handle }
+    /// Indexes `Mixer::BUSES`, for menus that cycle through the volumes.
+    pub fn bus_volume(&mut self, idx: usize) -> &mut f32 {
+        match idx {
+            0 => &mut self.master,
+            1 => &mut self.music,
+            _ => &mut self.sfx
+        }
+    }
+    /// Dips the music, which then recovers by itself in `update`.
+    pub fn duck(&mut self) {
+        if self.ducking {
+            self.duck = self.duck_depth;
+        }
+    }
+    /// Sets a music filter parameter, fading to it over `secs` seconds (instantly if `secs <= 0`).
+    pub fn fade_filter(&mut self, param: FilterParam, to: f32, secs: f32) {
+        let mut sl = self.sl.lock().unwrap();
+        let h = self.music_handle;
+        macro_rules! apply {
+            ($id:expr, $attr:expr) => {
+                if secs > 0.0 { sl.fade_filter_param(h, $id, $attr, to, secs as f64) }
+                else { sl.set_filter_param(h, $id, $attr, to) }
+            };
+        }
+        match param {
+            FilterParam::LowPassWet => apply!(LOWPASS_FILTER, BiquadResonantFilterAttr::Wet),
+            FilterParam::LowPassFreq => apply!(LOWPASS_FILTER, BiquadResonantFilterAttr::Freq),
+            FilterParam::LowPassResonance => apply!(LOWPASS_FILTER, BiquadResonantFilterAttr::Resonance),
+            FilterParam::EchoWet => apply!(ECHO_FILTER, EchoFilterAttr::Wet),
+            FilterParam::EchoDecay => apply!(ECHO_FILTER, EchoFilterAttr::Decay),
+        }
+    }
+    /// Turns both music filters back to fully dry.
+    pub fn reset_filters(&mut self) {
+        self.fade_filter(FilterParam::LowPassWet, 0.0, 0.0);
+        self.fade_filter(FilterParam::LowPassFreq, LOWPASS_OPEN, 0.0);
+        self.fade_filter(FilterParam::LowPassResonance, 2.0, 0.0);
+        self.fade_filter(FilterParam::EchoWet, 0.0, 0.0);
+        self.fade_filter(FilterParam::EchoDecay, 0.5, 0.0);
+    }
+    /// Muffles the music, then lets it clear up over `secs` seconds.
+    pub fn muffle(&mut self, secs: f32) {
+        if self.muffling {
+            self.fade_filter(FilterParam::LowPassFreq, self.muffle_freq, 0.0);
+            self.fade_filter(FilterParam::LowPassWet, 1.0, 0.0);
+            self.fade_filter(FilterParam::LowPassWet, 0.0, secs);
+        }
+    }
+    /// Current FFT of the music bus, see `Spectrum`.
+    pub fn fft(&self) -> Vec<f32> { self.music_bus.calc_fft() }
+    pub fn music_volume(&self) -> f32 { self.music_bus.approximate_volume(0) }
+    /// Call once per frame.
+    pub fn update(&mut self, frame_time: f32) {
+        self.duck = (self.duck - self.duck_recovery * frame_time).max(0.0);
+        let mut sl = self.sl.lock().unwrap();
+        sl.set_global_volume(self.master.clamp(0.0, 1.0));
+        sl.set_volume(self.music_handle, self.music.clamp(0.0, 1.0) * (1.0 - self.duck));
+        sl.set_volume(self.sfx_handle, self.sfx.clamp(0.0, 1.0));
+    }
+}
 
 pub struct SfxCreator {
-    sl: ThreadSafe<Soloud>
+    sl: ThreadSafe<Soloud>,
+    bus: Handle,
+    /// `None` marks a sound that failed to load, so it isn't retried every time it's played.
+    cache: HashMap<&'static str, Option<Wav>>,
+    volumes: HashMap<SfxCategory, f32>,
+    /// Where positioned sounds are heard from (the player), on screen like the sounds' positions by the time they're played.
+    pub listener: Vec2,
+    /// Distance in pixels from the listener at which a positioned sound is at half volume.
+    pub falloff: f32,
+    /// How far positioned sounds get panned at the screen edges (0-1).
+    pub pan_width: f32,
 }
 impl SfxCreator {
-    pub fn new(sl: ThreadSafe<Soloud>) -> Self { SfxCreator { sl } }
+    pub fn new(sl: ThreadSafe<Soloud>, bus: Handle) -> Self {
+        SfxCreator { sl, bus, cache: HashMap::new(), volumes: HashMap::new(), listener: Vec2::ZERO, falloff: 800.0, pan_width: 0.8 }
+    }
     pub fn spawn_sfx(&self, sfx: &impl AudioExt) -> Handle { self.sl.lock().unwrap().play(sfx) }
+    pub fn volume(&self, category: SfxCategory) -> f32 {
+        *self.volumes.get(&category).unwrap_or(&1.0)
+    }
+    pub fn set_volume(&mut self, category: SfxCategory, volume: f32) {
+        self.volumes.insert(category, volume.max(0.0));
+    }
+    /// Loads a sound into the cache ahead of time, so the first play doesn't stall a frame.
+    pub fn preload(&mut self, name: &'static str) -> bool {
+        self.cache.entry(name).or_insert_with(|| {
+            let mut wav = Wav::default();
+            match wav.load(format!("{SFX_DIR}/{name}.wav")) {
+                Ok(()) => Some(wav),
+                Err(e) => {
+                    println!("could not load sfx \"{name}\": {e}");
+                    None
+                }
+            }
+        }).is_some()
+    }
+    pub fn play(&mut self, sfx: Sfx) -> Option<Handle> {
+        if !self.preload(sfx.name) { return None; }
+        let mut volume = sfx.volume * self.volume(sfx.category);
+        let mut pan = 0.0;
+        if let Some(pos) = sfx.pos {
+            pan = ((pos.x / screen_width()) * 2.0 - 1.0).clamp(-1.0, 1.0) * self.pan_width;
+            volume *= self.falloff / (self.falloff + pos.distance(self.listener));
+        }
+        let wav = self.cache[sfx.name].as_ref()?;
+        Some(self.sl.lock().unwrap().play_ex(wav, volume, pan, false, self.bus))
+    }
+}
+
+/// Optional click track on the music's beat, for practicing and for checking chart offsets.
+pub struct Metronome {
+    pub enabled: bool,
+    pub beats_per_bar: usize,
+    /// Last whole beat clicked on.
+    last: Option<i64>,
+}
+impl Default for Metronome {
+    fn default() -> Self {
+        Metronome { enabled: false, beats_per_bar: 4, last: None }
+    }
+}
+impl Metronome {
+    /// The click to play for `beat`, if it's the first time a new beat has been reached.\
+    /// Beat 0 and every `beats_per_bar` beats from it are accented.
+    pub fn tick(&mut self, beat: f32) -> Option<Sfx> {
+        let whole = beat.floor() as i64;
+        if self.last == Some(whole) { return None; }
+        // the first beat seen (or a seek) only syncs up, otherwise starting partway through a beat would click off-beat
+        let fresh = self.last.is_some_and(|last| whole == last + 1);
+        self.last = Some(whole);
+        if !self.enabled || !fresh { return None; }
+        Some(if self.is_accent(whole) { Sfx::ui("click_accent") } else { Sfx::ui("click") })
+    }
+    pub fn is_accent(&self, beat: i64) -> bool {
+        beat.rem_euclid(self.beats_per_bar as i64) == 0
+    }
+}
+
+/// A track layered on top of the main one. It plays through its own bus into the music bus,
+/// so that it can be faded and measured on its own.
+pub struct Stem {
+    pub bus: Bus,
+    bus_handle: Handle,
 }
 
 pub struct Music {
     sl: ThreadSafe<Soloud>,
+    bus: Handle,
+    /// The main track. Timing comes from this one.
     handle: Option<Handle>,
+    /// Voice group of the main track and its stems, so that they start, seek and change speed as one.
+    group: Option<Handle>,
+    stems: Vec<Stem>,
+    /// Stems of the track fading out, kept until the next one fades out so that their buses live through the fade.
+    old_stems: Vec<Stem>,
     bpm: f32,
     offset: f32,
-    sought: f32,
+    /// Speed the level was loaded at.
     speed: f32,
+    /// Multiplier on top of `speed` set by the level, for slow-motion and speed-up sections.
+    scale: f32,
+    /// Length of the track in seconds if it loops, so the beat keeps counting up across loops.
+    loop_length: Option<f32>,
 }
 impl Music {
-    pub fn new(sl: ThreadSafe<Soloud>) -> Self {
-        Music { sl, handle: None, bpm: 0.0, offset: 0.0, sought: 0.0, speed: 1.0 }
+    pub fn new(sl: ThreadSafe<Soloud>, bus: Handle) -> Self {
+        Music {
+            sl, bus, handle: None, group: None, stems: vec![], old_stems: vec![],
+            bpm: 0.0, offset: 0.0, speed: 1.0, scale: 1.0, loop_length: None
+        }
     }
+    /// Everything that plays along with the main track.
+    fn voices(&self) -> Option<Handle> {
+        self.group.or(self.handle)
+    }
+    /// Sets up a new track, paused so stems can be added in sync; call `start` once they are.
     pub fn replace(&mut self, new_music: &impl AudioExt, bpm: f32, offset: f32) -> Handle {
-        if let Some(handle) = self.handle { self.sl.lock().unwrap().stop(handle); }
-        let handle = self.sl.lock().unwrap().play(new_music);
-        //self.sl.lock().unwrap().seek(handle, offset as f64 * self.bpm as f64 / 60.0);
+        let mut sl = self.sl.lock().unwrap();
+        if let Some(voices) = self.voices() { sl.stop(voices); }
+        if let Some(group) = self.group.take() { sl.destroy_voice_group(group).ok(); }
+        self.stems.clear();
+        let handle = sl.play_ex(new_music, 1.0, 0.0, true, self.bus);
+        let group = sl.create_voice_group();
+        sl.add_voice_to_group(group, handle).ok();
+        self.group = Some(group);
         self.handle = Some(handle);
         self.bpm = bpm;
         self.offset = offset;
-        self.sought = 0.0;
+        self.scale = 1.0;
+        self.loop_length = None;
         handle
     }
+    /// Adds a stem to the current track, returning its index. Only in sync if added before `start`.
+    pub fn add_stem(&mut self, stem: &impl AudioExt) -> Option<usize> {
+        let group = self.group?;
+        let mut bus = Bus::default();
+        bus.set_visualize_enable(true);
+        let mut sl = self.sl.lock().unwrap();
+        let bus_handle = sl.play_ex(&bus, 1.0, 0.0, false, self.bus);
+        let voice = sl.play_ex(stem, 1.0, 0.0, true, bus_handle);
+        sl.add_voice_to_group(group, voice).ok();
+        self.stems.push(Stem { bus, bus_handle });
+        Some(self.stems.len() - 1)
+    }
+    /// Unpauses the track and all of its stems at once.
+    pub fn start(&mut self) {
+        if let Some(voices) = self.voices() {
+            self.sl.lock().unwrap().set_pause(voices, false);
+        }
+    }
+    pub fn stems(&self) -> &[Stem] { &self.stems }
+    /// Fades stem `idx` to `volume` over `secs` seconds (instantly if `secs <= 0`).
+    pub fn fade_stem(&mut self, idx: usize, volume: f32, secs: f32) {
+        let Some(stem) = self.stems.get(idx) else { return; };
+        let mut sl = self.sl.lock().unwrap();
+        if secs > 0.0 {
+            sl.fade_volume(stem.bus_handle, volume, secs as f64);
+        } else {
+            sl.set_volume(stem.bus_handle, volume);
+        }
+    }
+    /// Rough loudness of every stem this frame, 0 for silence.
+    pub fn stem_energies(&self) -> Vec<f32> {
+        self.stems.iter().map(|stem| stem.bus.approximate_volume(0)).collect()
+    }
     pub fn speed(&mut self, speed: f32) -> Option<Result<(), SoloudError>> {
         self.speed = speed;
-        if let Some(handle) = self.handle {
+        if let Some(voices) = self.voices() {
             let mut guard = self.sl.lock().unwrap();
-            Some(guard.set_relative_play_speed(handle, speed))
+            Some(guard.set_relative_play_speed(voices, speed * self.scale))
         } else {
             None
         }
     }
-    pub fn get_speed(&self) -> f32 { self.speed }
+    /// Loops the current track forever. `length` is the length of the track in seconds.
+    pub fn set_looping(&mut self, length: f32) {
+        if let Some(voices) = self.voices() {
+            self.sl.lock().unwrap().set_looping(voices, true);
+            self.loop_length = Some(length);
+        }
+    }
+    /// Eases the playback speed to `scale` times the load speed over `beats` beats.\
+    /// The beat clock follows the track's position, so it stays in sync through the change.
+    pub fn fade_speed(&mut self, scale: f32, beats: f32) {
+        // a stopped track would never get to the end of the fade
+        let scale = scale.max(0.05);
+        let (from, to) = (self.get_speed(), self.speed * scale);
+        self.scale = scale;
+        if let Some(voices) = self.voices() {
+            let mut sl = self.sl.lock().unwrap();
+            if beats > 0.0 && self.bpm > 0.0 {
+                // the speed moves linearly, so the beats covered are the duration times the average speed
+                let secs = beats * 60.0 / self.bpm / ((from + to) / 2.0);
+                sl.fade_relative_play_speed(voices, to, secs as f64);
+            } else {
+                sl.set_relative_play_speed(voices, to).ok();
+            }
+        }
+    }
+    /// The speed the track is actually playing at right now, partway through a `fade_speed` included.
+    pub fn get_speed(&self) -> f32 {
+        match self.handle {
+            Some(h) => self.sl.lock().unwrap().relative_play_speed(h),
+            None => self.speed * self.scale
+        }
+    }
+    /// The speed the current `fade_speed` is heading to, relative to the load speed.
+    pub fn get_scale(&self) -> f32 { self.scale }
+    /// Forgets the current track (and its stems) without stopping it, returning the handle for all of its voices.\
+    /// That's a voice group, so `destroy_voice_group` it once done with it.
     pub fn stop(&mut self) -> Option<Handle> {
-        if let Some(handle) = self.handle { self.handle = None; Some(handle) }
-        else { None }
+        let voices = self.voices();
+        self.handle = None;
+        self.group = None;
+        self.old_stems = std::mem::take(&mut self.stems);
+        voices
+    }
+    /// Brings the current track up from silence over `secs` seconds.
+    pub fn fade_in(&mut self, secs: f32) {
+        if let Some(voices) = self.voices() {
+            let mut sl = self.sl.lock().unwrap();
+            sl.set_volume(voices, 0.0);
+            sl.fade_volume(voices, 1.0, secs as f64);
+        }
+    }
+    /// Like `stop`, but the track keeps fading out in the background for `secs` seconds before it actually stops.
+    pub fn fade_out(&mut self, secs: f32) -> Option<Handle> {
+        let voices = self.stop()?;
+        let sl = self.sl.lock().unwrap();
+        sl.fade_volume(voices, 0.0, secs as f64);
+        sl.schedule_stop(voices, secs as f64);
+        // fades and stops are set on each voice, so the group isn't needed anymore
+        sl.destroy_voice_group(voices).ok();
+        Some(voices)
+    }
+    /// Seconds into the track, as in how much of it has been played (seeks and play speed included).
+    pub fn position(&self) -> Option<f32> {
+        let sl = self.sl.lock().unwrap();
+        Some(sl.stream_position(self.handle?) as f32)
     }
     pub fn current_beat(&self) -> Option<f32> {
         match self.handle {
@@ -56,9 +480,12 @@ impl Music {
                 let sl = self.sl.lock().unwrap();
                 let sr = sl.samplerate(h);
                 let buf_size = sl.backend_buffer_size() as f32;
-                let offset = buf_size / sr;
-                
-                let beat = ((sl.stream_time(h) as f32 + offset) * self.bpm / 60.0 + self.offset + self.sought) * self.speed;
+                // one backend buffer of latency, in track seconds
+                let latency = buf_size / sr * sl.relative_play_speed(h);
+
+                let looped = self.loop_length.map_or(0.0, |length| sl.loop_count(h) as f32 * length);
+
+                let beat = (sl.stream_position(h) as f32 + looped + latency) * self.bpm / 60.0 + self.offset;
                 Some(beat)
             }
             None => None
@@ -67,7 +494,11 @@ impl Music {
     pub fn check(&mut self) {
         if let Some(handle) = self.handle {
             if !self.sl.lock().unwrap().is_valid_voice_handle(handle) {
-                self.handle = None;
+                if let Some(voices) = self.stop() {
+                    let sl = self.sl.lock().unwrap();
+                    sl.stop(voices);
+                    sl.destroy_voice_group(voices).ok();
+                }
             }
         }
     }
@@ -78,12 +509,59 @@ impl Music {
             false
         }
     }
+    /// Jumps to `beats` beats into the track (not counting the offset).\
+    /// Streams decode from the start when seeking backwards, so that can take a moment on long songs.
     pub fn seek(&mut self, beats: f32) -> Result<(), SoloudError> {
-        if let Some(h) = self.handle {
+        if let Some(voices) = self.voices() {
             let sl = self.sl.lock().unwrap();
-            sl.seek(h, (beats * 60.0 / self.bpm) as f64)?;
-            self.sought += beats;
+            sl.seek(voices, (beats * 60.0 / self.bpm) as f64)?;
         }
         Ok(())
     }
 }
+
+/// Song previews for the level select, played on the music bus and crossfaded between.
+pub struct Preview {
+    sl: ThreadSafe<Soloud>,
+    bus: Handle,
+    /// The file being previewed, its stream and its voice.
+    current: Option<(String, WavStream, Handle)>,
+    /// Previews on their way out. The streams are kept alive until their voices have faded out.
+    fading: Vec<(WavStream, Handle)>,
+}
+impl Preview {
+    pub fn new(sl: ThreadSafe<Soloud>, bus: Handle) -> Self {
+        Preview { sl, bus, current: None, fading: vec![] }
+    }
+    /// Crossfades to `path`, starting `start` seconds in and looping. Does nothing if `path` is already playing.
+    pub fn play(&mut self, path: &str, start: f32, fade: f32) -> Result<(), SoloudError> {
+        if self.current.as_ref().is_some_and(|(current, _, _)| current == path) { return Ok(()); }
+        self.stop(fade);
+        let mut stream = WavStream::default();
+        stream.load(path)?;
+        let mut sl = self.sl.lock().unwrap();
+        // started paused so the seek happens before anything is heard
+        let handle = sl.play_ex(&stream, 0.0, 0.0, true, self.bus);
+        sl.seek(handle, start as f64)?;
+        sl.set_looping(handle, true);
+        sl.fade_volume(handle, 1.0, fade as f64);
+        sl.set_pause(handle, false);
+        drop(sl);
+        self.current = Some((path.to_string(), stream, handle));
+        Ok(())
+    }
+    /// Fades the current preview out over `fade` seconds.
+    pub fn stop(&mut self, fade: f32) {
+        if let Some((_, stream, handle)) = self.current.take() {
+            let sl = self.sl.lock().unwrap();
+            sl.fade_volume(handle, 0.0, fade as f64);
+            sl.schedule_stop(handle, fade as f64);
+            self.fading.push((stream, handle));
+        }
+    }
+    /// Drops previews that have finished fading out.
+    pub fn update(&mut self) {
+        let sl = self.sl.lock().unwrap();
+        self.fading.retain(|(_, handle)| sl.is_valid_voice_handle(*handle));
+    }
+}
@@ -1,8 +1,8 @@
 
-use macroquad::{window::{screen_width, screen_height}, prelude::{Vec2, rand::gen_range, vec2}};
+use macroquad::prelude::{Vec2, rand::gen_range, vec2};
 use rand::{thread_rng, Rng};
 
-use crate::game::{ModifyArgs, UpdateAccumulator};
+use crate::{game::{ModifyArgs, UpdateAccumulator}, utils::{screen_width, screen_height}};
 
 use super::{game::{GameState, Accumulatee}, game_objects::{Bomb, Obst, GrowLaser}};
 
@@ -3,14 +3,18 @@ use std::{error::Error, fmt::Display};
 use macroquad::color::Color;
 use soloud::{Wav, AudioExt, LoadExt};
 
-use crate::{game::{GameState, LevelState, ColorEase, StateModifier, ModifyArgs}, sound::Music};
+use crate::{CanErr, chart::Recorder, game::{GameState, LevelState, Results, ColorEase, StateModifier, ModifyArgs}, sound::Music};
 
 pub type LevelInfo = (f32, f32, &'static str);
-pub type LevelLoader = fn(&mut GameState) -> LevelInfo;
+pub type LevelLoader = fn(&mut GameState) -> CanErr;
 
 pub enum EparState {
     MainMenu,
-    InGame(LevelState)
+    InGame(Box<LevelState>),
+    /// Playing a level's song with no events, recording a chart for it.
+    Recording(Recorder),
+    /// After finishing a level.
+    Results(Results),
 }
 impl EparState {
     pub fn map<R, F: FnOnce(&mut LevelState) -> R>(&mut self, map_fn: F) -> Option<R> {
@@ -47,6 +51,19 @@ impl StateModifier for ColorChange {
     }
 }
 
+/// Eases the music (and with it, everything else) to `scale` times the speed the level was loaded at, over `beats` beats.
+#[derive(Clone, Copy)]
+pub struct SpeedChange {
+    pub scale: f32,
+    pub beats: f32,
+}
+impl StateModifier for SpeedChange {
+    fn box_clone(&self) -> Box<dyn StateModifier> { Box::new(*self) }
+    fn run(&self, state: &mut GameState, _: ModifyArgs) {
+        state.mus.fade_speed(self.scale, self.beats);
+    }
+}
+
 macro_rules! pat_lvls {
     ($($lvl:tt),+) => {
         $(
@@ -96,6 +113,28 @@ impl EparLevel {
             EparLevel::Firestarter => "Firestarter",
         }
     }
+    /// Offset (in beats), bpm and song of the level, which menus can look up without loading it.
+    pub fn info(self) -> LevelInfo {
+        match self {
+            EparLevel::MoonlightSonata => (-9.732721 * 177.5 / 60.0, 177.5, "./music/moonlight_sonata.mp3"),
+            EparLevel::FriendlyFaithPlate => (-2.05, 120.0, "music/[120] friendly_faith_plate.mp3"),
+            EparLevel::Smoke => (-1.678 * 74.0 / 60.0, 74.0, "./music/smoke.mp3"),
+            EparLevel::Granite => (0.05 / 128.0 * 60.0, 128.0, "music/granite.mp3"),
+            EparLevel::Inferno => (0.0, 170.0, "music/inferno.mp3"),
+            EparLevel::Isolation => (-8.442 * 200.0 / 60.0, 200.0, "music/isolation.mp3"),
+            EparLevel::Kocmoc => (-21.294 * 95.0 / 60.0, 95.0, "music/kocmoc2.mp3"),
+            EparLevel::Sparkler => (-17.886 * 108.5 / 60.0, 108.5, "music/sparkler.mp3"),
+            EparLevel::Firestarter => (-1.978 * 135.0 / 60.0, 135.0, "music/firestarter.mp3"),
+        }
+    }
+    /// Beat of the level that the menu preview of its song starts on.
+    pub fn preview_beat(&self) -> f32 {
+        match self {
+            EparLevel::FriendlyFaithPlate => 8.0,
+            EparLevel::Smoke => 28.0,
+            _ => 0.0
+        }
+    }
     /// Used to filter out levels that are under development
     pub fn finished(&self) -> bool {
         match self {
@@ -0,0 +1,54 @@
+//! Eased moves of a value over some beats, starting from wherever the value is when the move starts.\
+//! The camera and the arena are animated through these.
+use macroquad::prelude::{Vec2, Rect};
+
+use crate::{game_objects::Easing, utils::lerp};
+
+/// Something a `Tween` can move between.
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self { lerp(self, to, t) }
+}
+impl Lerp for Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self { Vec2::lerp(self, to, t) }
+}
+impl Lerp for Rect {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Rect::new(lerp(self.x, to.x, t), lerp(self.y, to.y, t), lerp(self.w, to.w, t), lerp(self.h, to.h, t))
+    }
+}
+
+/// An eased move to `to`, over `beats` beats from `start`.
+pub struct Tween<T> {
+    pub to: T,
+    pub start: f32,
+    pub beats: f32,
+    pub ease: Box<dyn Easing>,
+    from: Option<T>,
+}
+impl<T: Lerp> Tween<T> {
+    pub fn new(to: T, start: f32, beats: f32, ease: Box<dyn Easing>) -> Self {
+        Tween { to, start, beats, ease, from: None }
+    }
+    /// 0-1 through the move at `time` (in beats).
+    pub fn progress(&self, time: f32) -> f32 {
+        if self.beats <= 0.0 { (time >= self.start) as u8 as f32 } else { ((time - self.start) / self.beats).clamp(0.0, 1.0) }
+    }
+    pub fn finished(&self, time: f32) -> bool {
+        self.progress(time) >= 1.0
+    }
+    /// Where the value should be at `time`, or `None` if the move hasn't started.\
+    /// The first time it's started, `current` is taken as where it's moving from.
+    pub fn step(&mut self, time: f32, current: T) -> Option<T> {
+        if time < self.start { return None; }
+        let from = *self.from.get_or_insert(current);
+        Some(from.lerp(self.to, self.ease.run(self.progress(time))))
+    }
+}
+impl<T: Copy> Clone for Tween<T> {
+    fn clone(&self) -> Self {
+        Tween { ease: self.ease.box_clone(), ..*self }
+    }
+}
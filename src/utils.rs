@@ -1,9 +1,9 @@
 #![allow(dead_code)]
 use std::{f32::consts::{TAU, PI}, ops::Add};
 
-use macroquad::{prelude::{Vec2, vec2, Color}, shapes::draw_triangle, text::{draw_text, measure_text}, window::{screen_width, screen_height}, rand::gen_range};
+use macroquad::{prelude::{Vec2, vec2, Color, Rect, Camera2D, BLACK}, shapes::{draw_triangle, draw_rectangle}, text::{draw_text, measure_text}, window::{self, clear_background}, input::mouse_position, camera::set_camera, rand::gen_range};
 
-use crate::game::GSEvent;
+use crate::{game::GSEvent, sat};
 
 /// AABB:circle collision 9-patch checks
 const CA_COLL: [fn(Vec2, Vec2, Vec2, f32) -> bool; 9] = [
@@ -70,9 +70,10 @@ pub fn mix(color1: Color, color2: Color, by: f32) -> Color {
 #[inline]
 pub fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }
 
-// Tests if two rotatable rectangles collide.\
-// TODO: implement
-// pub fn collide_rr(center1: Vec2, size1: Vec2, rot1: f32, center2: Vec2, size2: Vec2, rot2: f32) -> bool { false }
+/// Tests if two rotatable rectangles collide. Rotations work like `collide_cr`.
+pub fn collide_rr(center1: Vec2, size1: Vec2, rot1: f32, center2: Vec2, size2: Vec2, rot2: f32) -> bool {
+    sat::collide_pp(&sat::rect_corners(center1, size1, rot1), &sat::rect_corners(center2, size2, rot2))
+}
 
 /// Tests if a circle is colliding with a rotatable rectangle.
 pub fn collide_cr(rcenter: Vec2, rsize: Vec2, rot: f32, cpos: Vec2, rad: f32) -> bool {
@@ -142,6 +143,51 @@ pub fn gay(phase: f32) -> Color {
     }
 }
 
+/// Levels are laid out in this many units whatever size the window is, see `letterbox`.
+pub const VIRTUAL_WIDTH: f32 = 1600.0;
+pub const VIRTUAL_HEIGHT: f32 = 900.0;
+
+/// Width of the virtual screen everything is laid out in, not of the window.
+pub fn screen_width() -> f32 { VIRTUAL_WIDTH }
+/// Height of the virtual screen everything is laid out in, not of the window.
+pub fn screen_height() -> f32 { VIRTUAL_HEIGHT }
+
+/// Where the virtual screen is in the window, in window pixels: as big as fits, in the middle.
+pub fn letterbox() -> Rect {
+    let window = vec2(window::screen_width(), window::screen_height());
+    let size = screen_size() * (window / screen_size()).min_element();
+    let pos = (window - size) / 2.0;
+    Rect::new(pos.x, pos.y, size.x, size.y)
+}
+
+/// Converts window pixels (like the mouse) to virtual units.
+pub fn to_virtual(pos: Vec2) -> Vec2 {
+    let lb = letterbox();
+    (pos - vec2(lb.x, lb.y)) / vec2(lb.w, lb.h) * screen_size()
+}
+
+/// The mouse position in virtual units.
+pub fn mouse_pos() -> Vec2 {
+    to_virtual(mouse_position().into())
+}
+
+/// A camera that shows the virtual screen in the letterbox.
+pub fn virtual_camera() -> Camera2D {
+    let lb = letterbox();
+    Camera2D {
+        viewport: Some((lb.x as i32, lb.y as i32, lb.w as i32, lb.h as i32)),
+        ..Camera2D::from_display_rect(Rect::new(0.0, 0.0, screen_width(), screen_height()))
+    }
+}
+
+/// Clears the window to black bars around the virtual screen filled with `color`, and sets up drawing in virtual units.\
+/// Call at the start of drawing a frame.
+pub fn clear_virtual(color: Color) {
+    clear_background(BLACK);
+    set_camera(&virtual_camera());
+    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), color);
+}
+
 pub fn screen_center() -> Vec2 {
     screen_size() / 2.0
 }